@@ -0,0 +1,39 @@
+-- Reports the files xmake-rs will install for the selected targets, relative
+-- to the install directory.
+-- The output is printed between markers as `key:value1|value2` lines.
+import("core.project.config")
+import("core.project.project")
+
+function main()
+    os.cd(os.projectdir())
+    config.load()
+
+    local artifacts = {}
+    for _, target in ipairs(_get_targets()) do
+        local targets = {target}
+        table.join2(targets, target:orderdeps())
+
+        for _, t in ipairs(targets) do
+            local filename = path.filename(t:targetfile())
+            if t:is_static() then
+                table.insert(artifacts, "lib/" .. filename)
+            elseif t:is_shared() then
+                if t:is_plat("windows", "mingw") then
+                    table.insert(artifacts, "bin/" .. filename)
+                    local implib = t:artifactfile("implib")
+                    if implib then
+                        table.insert(artifacts, "lib/" .. path.filename(implib))
+                    end
+                else
+                    table.insert(artifacts, "lib/" .. filename)
+                end
+            elseif t:is_binary() then
+                table.insert(artifacts, "bin/" .. filename)
+            end
+        end
+    end
+
+    print("__xmakers_start__")
+    print("artifacts:" .. table.concat(table.unique(artifacts), "|"))
+    print("__xmakers_end__")
+end
@@ -0,0 +1,157 @@
+-- Reports the information needed by xmake-rs to link the built targets.
+-- The output is printed between markers as `key:value1|value2` lines.
+import("core.base.option")
+import("core.project.config")
+import("core.project.project")
+
+-- Returns the name to link the library of the target with. For the DLLs, it
+-- is the name of their import library, `foo.lib` or `libfoo.dll.a`, which
+-- may differ from the name of the DLL itself.
+function _linkname(t)
+    if t:is_shared() and t:is_plat("windows", "mingw") then
+        local implib = t:artifactfile("implib")
+        if implib then
+            local name = path.filename(implib)
+            if name:endswith(".dll.a") then
+                name = name:sub(1, -7)
+                if name:startswith("lib") then
+                    name = name:sub(4)
+                end
+                return name
+            elseif name:endswith(".lib") then
+                return name:sub(1, -5)
+            end
+        end
+    end
+    return t:linkname()
+end
+
+-- Appends `value` to `list` if not already present.
+function _append(list, value)
+    if value and not table.contains(list, value) then
+        table.insert(list, value)
+    end
+end
+
+function main()
+    os.cd(os.projectdir())
+    config.load()
+    project.load_targets()
+
+    local linkdirs = {}
+    local frameworkdirs = {}
+    local links = {}
+    local links_target = {}
+    local links_package = {}
+    local syslinks = {}
+    local packages = {}
+    local includedirs_package = {}
+    local includedirs_target = {}
+    local cxx_used = false
+
+    for _, target in ipairs(_get_targets()) do
+        -- The dependencies are linked after the target that depends on them.
+        local targets = {target}
+        table.join2(targets, target:orderdeps())
+
+        for _, t in ipairs(targets) do
+            local tlinks = links_target[t:name()] or {}
+            links_target[t:name()] = tlinks
+
+            local kind = t:kind()
+            if kind == "static" or kind == "shared" then
+                _append(links, _linkname(t) .. "/" .. kind)
+                _append(tlinks, _linkname(t) .. "/" .. kind)
+            end
+
+            for _, link in ipairs(table.wrap(t:get("links"))) do
+                _append(links, link .. "/unknown")
+                _append(tlinks, link .. "/unknown")
+            end
+            for _, linkdir in ipairs(table.wrap(t:get("linkdirs"))) do
+                _append(linkdirs, path.absolute(linkdir, os.projectdir()))
+            end
+            for _, framework in ipairs(table.wrap(t:get("frameworks"))) do
+                _append(links, framework .. "/framework")
+                _append(tlinks, framework .. "/framework")
+            end
+            for _, frameworkdir in ipairs(table.wrap(t:get("frameworkdirs"))) do
+                _append(frameworkdirs, path.absolute(frameworkdir, os.projectdir()))
+            end
+            for _, syslink in ipairs(table.wrap(t:get("syslinks"))) do
+                _append(syslinks, syslink)
+            end
+
+            local includedirs = {}
+            for _, includedir in ipairs(table.wrap(t:get("includedirs"))) do
+                _append(includedirs, path.absolute(includedir, os.projectdir()))
+            end
+            includedirs_target[t:name()] = includedirs
+
+            if table.contains(t:sourcekinds(), "cxx") then
+                cxx_used = true
+            end
+
+            for _, pkg in ipairs(t:orderpkgs()) do
+                _append(packages, pkg:name() .. "/" .. (pkg:version_str() or ""))
+
+                local plinks = links_package[pkg:name()] or {}
+                links_package[pkg:name()] = plinks
+
+                local pkgkind = pkg:config("shared") and "shared" or "static"
+                for _, link in ipairs(table.wrap(pkg:get("links"))) do
+                    _append(links, link .. "/" .. pkgkind)
+                    _append(plinks, link .. "/" .. pkgkind)
+                end
+                -- A header-only package is reported so that its include
+                -- directories can be told apart
+                if #table.wrap(pkg:get("links")) == 0 and #table.wrap(pkg:get("libfiles")) == 0 then
+                    _append(links, pkg:name() .. "/headeronly")
+                    _append(plinks, pkg:name() .. "/headeronly")
+                end
+                for _, linkdir in ipairs(table.wrap(pkg:get("linkdirs"))) do
+                    _append(linkdirs, linkdir)
+                end
+                for _, framework in ipairs(table.wrap(pkg:get("frameworks"))) do
+                    _append(links, framework .. "/framework")
+                    _append(plinks, framework .. "/framework")
+                end
+                for _, frameworkdir in ipairs(table.wrap(pkg:get("frameworkdirs"))) do
+                    _append(frameworkdirs, frameworkdir)
+                end
+                for _, syslink in ipairs(table.wrap(pkg:get("syslinks"))) do
+                    _append(syslinks, syslink)
+                end
+
+                local pkgincludedirs = {}
+                for _, includedir in ipairs(table.join(table.wrap(pkg:get("includedirs")), table.wrap(pkg:get("sysincludedirs")))) do
+                    _append(pkgincludedirs, includedir)
+                end
+                includedirs_package[pkg:name()] = pkgincludedirs
+            end
+        end
+    end
+
+    print("__xmakers_start__")
+    print("linkdirs:" .. table.concat(linkdirs, "|"))
+    print("frameworkdirs:" .. table.concat(frameworkdirs, "|"))
+    print("links:" .. table.concat(links, "|"))
+    print("syslinks:" .. table.concat(syslinks, "|"))
+    print("packages:" .. table.concat(packages, "|"))
+    for name, tlinks in pairs(links_target) do
+        print("links_target." .. name .. ":" .. table.concat(tlinks, "|"))
+    end
+    for name, plinks in pairs(links_package) do
+        print("links_package." .. name .. ":" .. table.concat(plinks, "|"))
+    end
+    for name, dirs in pairs(includedirs_target) do
+        print("includedirs_target." .. name .. ":" .. table.concat(dirs, "|"))
+    end
+    for name, dirs in pairs(includedirs_package) do
+        print("includedirs_package." .. name .. ":" .. table.concat(dirs, "|"))
+    end
+    print("cxx_used:" .. tostring(cxx_used))
+    -- The C++ standard library is needed as soon as C++ is used
+    print("stl_used:" .. tostring(cxx_used))
+    print("__xmakers_end__")
+end
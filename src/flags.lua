@@ -0,0 +1,45 @@
+-- Reports the flags xmake passes to the compilers and the linker for the
+-- selected targets.
+-- The output is printed between markers as `key:value1|value2` lines.
+import("core.project.config")
+import("core.project.project")
+import("core.tool.compiler")
+import("core.tool.linker")
+
+-- Appends the values of `values` to `list` if not already present.
+function _append_all(list, values)
+    for _, value in ipairs(table.wrap(values)) do
+        if not table.contains(list, value) then
+            table.insert(list, value)
+        end
+    end
+end
+
+function main()
+    os.cd(os.projectdir())
+    config.load()
+    project.load_targets()
+
+    local cflags = {}
+    local cxxflags = {}
+    local ldflags = {}
+    for _, target in ipairs(_get_targets()) do
+        local sourcekinds = target:sourcekinds()
+        -- The flags only depend on the kind of the source file, not on its name
+        if table.contains(sourcekinds, "cc") then
+            _append_all(cflags, compiler.compflags("xmakers.c", {target = target, sourcekind = "cc"}))
+        end
+        if table.contains(sourcekinds, "cxx") then
+            _append_all(cxxflags, compiler.compflags("xmakers.cpp", {target = target, sourcekind = "cxx"}))
+        end
+        if target:is_binary() or target:is_shared() then
+            _append_all(ldflags, linker.linkflags(target:kind(), sourcekinds, {target = target}))
+        end
+    end
+
+    print("__xmakers_start__")
+    print("cflags:" .. table.concat(cflags, "|"))
+    print("cxxflags:" .. table.concat(cxxflags, "|"))
+    print("ldflags:" .. table.concat(ldflags, "|"))
+    print("__xmakers_end__")
+end
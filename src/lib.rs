@@ -21,511 +21,7470 @@
 //! use xmake;
 //!
 //! // Builds the project in the directory located in `libfoo`, installing it
-//! // into $OUT_DIR
-//! let dst = xmake::build("libfoo");
-//!
-//! println!("cargo:rustc-link-search=native={}", dst.display());
-//! println!("cargo:rustc-link-lib=static=foo");
+//! // into $OUT_DIR and linking the produced libraries
+//! xmake::build("libfoo");
 //! ```
 //!
+//! The produced libraries are linked by default. Build scripts printing their
+//! own `rustc-link-lib` and `rustc-link-search` directives must disable it
+//! with [`Config::auto_link`], and can read what xmake reported from
+//! [`Config::build_info`]:
+//!
 //! ```no_run
 //! use xmake::Config;
 //!
-//! let dst = Config::new("libfoo")
-//!                 .option("bar", "true")
-//!                 .env("XMAKE", "path/to/xmake")
-//!                 .build();
-//! println!("cargo:rustc-link-search=native={}", dst.display());
-//! println!("cargo:rustc-link-lib=static=foo");
+//! let mut config = Config::new("libfoo");
+//! config.option("bar", "true")
+//!       .env("XMAKE", "path/to/xmake")
+//!       .auto_link(false)
+//!       .build();
+//!
+//! for link in config.build_info().links() {
+//!     println!("cargo:rustc-link-lib={}", link.name());
+//! }
 //! ```
+//!
+//! ## Features
+//!
+//! - `log`: emits the commands run by the crate as `debug!` records and the
+//!   environment variables it reads as `trace!` records of the [`log`] crate.
+//!
+//! [`log`]: https://docs.rs/log
 #![deny(missing_docs)]
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::ffi::{OsStr, OsString};
-use std::io::ErrorKind;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-/// Builder style configuration for a pending XMake build.
-pub struct Config {
-    path: PathBuf,
-    target: Option<String>,
-    verbose: bool,
-    out_dir: Option<PathBuf>,
-    mode: Option<String>,
-    options: Vec<(OsString, OsString)>,
-    env: Vec<(OsString, OsString)>,
-    static_crt: Option<bool>,
-    cpp_link_stdlib: Option<String>,
-    env_cache: HashMap<String, Option<OsString>>,
+/// Represents the different kinds of linkage for a library.
+///
+/// # Examples
+///
+/// ```
+/// use xmake::LinkKind;
+///
+/// assert_eq!("dynamic".parse(), Ok(LinkKind::Dynamic));
+/// assert_eq!("shared".parse(), Ok(LinkKind::Dynamic));
+///
+/// let kinds = [
+///     LinkKind::Static,
+///     LinkKind::Dynamic,
+///     LinkKind::System,
+///     LinkKind::Framework,
+///     LinkKind::Unknown,
+///     LinkKind::HeaderOnly,
+/// ];
+/// for kind in kinds {
+///     assert_eq!(kind.to_string().parse(), Ok(kind));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkKind {
+    /// Static linkage (`.a`, `.lib`).
+    Static,
+    /// Dynamic linkage (`.so`, `.dylib`, `.dll`).
+    Dynamic,
+    /// A library provided by the system.
+    System,
+    /// A macOS framework.
+    Framework,
+    /// Unknown linkage.
+    Unknown,
+    /// A header-only package, which has nothing to link but include
+    /// directories.
+    HeaderOnly,
 }
 
-/// Builds the native library rooted at `path` with the default xmake options.
-/// This will return the directory in which the library was installed.
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            LinkKind::Static => "static",
+            LinkKind::Dynamic => "shared",
+            LinkKind::System => "system",
+            LinkKind::Framework => "framework",
+            LinkKind::Unknown => "unknown",
+            LinkKind::HeaderOnly => "headeronly",
+        };
+        f.write_str(kind)
+    }
+}
+
+impl FromStr for LinkKind {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "static" => Ok(LinkKind::Static),
+            "shared" | "dynamic" => Ok(LinkKind::Dynamic),
+            "system" => Ok(LinkKind::System),
+            "framework" => Ok(LinkKind::Framework),
+            "unknown" => Ok(LinkKind::Unknown),
+            "headeronly" => Ok(LinkKind::HeaderOnly),
+            _ => Err(ParsingError::InvalidKind),
+        }
+    }
+}
+
+/// Represents where a library to link against comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkOrigin {
+    /// The library is a target of the project, or linked by the target named
+    /// with `add_links`.
+    Target(String),
+    /// The library is provided by the package named.
+    Package(String),
+    /// The origin of the library is unknown.
+    Unknown,
+}
+
+/// Represents a single library to link against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Link {
+    name: String,
+    kind: LinkKind,
+    origin: LinkOrigin,
+}
+
+impl Link {
+    /// Creates a new link named `name` of kind `kind`, of unknown origin.
+    pub fn new(name: &str, kind: LinkKind) -> Link {
+        Link {
+            name: name.to_string(),
+            kind,
+            origin: LinkOrigin::Unknown,
+        }
+    }
+
+    /// Returns the name of the library.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the kind of linkage of the library.
+    pub fn kind(&self) -> LinkKind {
+        self.kind
+    }
+
+    /// Returns the target or package the library comes from.
+    pub fn origin(&self) -> &LinkOrigin {
+        &self.origin
+    }
+}
+
+impl fmt::Display for Link {
+    /// Formats the link as `name/kind`, as parsed by [`FromStr`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.name, self.kind)
+    }
+}
+
+impl FromStr for Link {
+    type Err = ParsingError;
+
+    /// Parses a link formatted as `name/kind`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, kind) = s.rsplit_once('/').ok_or(ParsingError::ParseError)?;
+        Ok(Link::new(name, kind.parse()?))
+    }
+}
+
+/// Represents a package resolved by xmake for the build.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Package {
+    name: String,
+    version: Option<String>,
+}
+
+impl Package {
+    /// Returns the name of the package.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version of the package, if it has one.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+impl fmt::Display for Package {
+    /// Formats the package as `name/version`, as parsed by [`FromStr`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.name, self.version.as_deref().unwrap_or(""))
+    }
+}
+
+impl FromStr for Package {
+    type Err = ParsingError;
+
+    /// Parses a package formatted as `name/version`, with an empty version
+    /// for the packages without one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, version) = s.rsplit_once('/').ok_or(ParsingError::ParseError)?;
+        Ok(Package {
+            name: name.to_string(),
+            version: (!version.is_empty()).then(|| version.to_string()),
+        })
+    }
+}
+
+/// Represents the source when querying information from [`BuildInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Information coming from the targets of the project.
+    Target,
+    /// Information coming from the packages required by the project.
+    Package,
+    /// Information coming from both targets and packages.
+    Both,
+}
+
+/// Rpath added to the Rust binaries by [`Config::rpath`], so they find the
+/// shared libraries at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RpathMode {
+    /// No rpath is added.
+    #[default]
+    None,
+    /// The absolute path of the install directory of the libraries, only valid
+    /// on the machine that built the binaries.
+    InstallDir,
+    /// The directory of the binary, `$ORIGIN` or `@loader_path` on Apple
+    /// platforms, to find the libraries copied next to it.
+    Origin,
+}
+
+/// Sanitizer enabled with [`Config::sanitizer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sanitizer {
+    /// AddressSanitizer, detecting memory errors.
+    Address,
+    /// UndefinedBehaviorSanitizer.
+    Undefined,
+    /// ThreadSanitizer, detecting data races.
+    Thread,
+    /// MemorySanitizer, detecting reads of uninitialized memory.
+    Memory,
+    /// LeakSanitizer, detecting memory leaks.
+    Leak,
+}
+
+impl Sanitizer {
+    /// Returns the name of the sanitizer, as used by `-fsanitize=` and the
+    /// `build.sanitizer.*` policies of xmake.
+    fn name(&self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+            Sanitizer::Thread => "thread",
+            Sanitizer::Memory => "memory",
+            Sanitizer::Leak => "leak",
+        }
+    }
+}
+
+/// Value of an xmake option set with [`Config::option`].
+///
+/// Strings and paths are kept as [`OsString`], so values that aren't valid
+/// UTF-8 are passed to xmake unchanged.
 ///
 /// # Examples
 ///
-/// ```no_run
-/// use xmake;
+/// ```
+/// use xmake::OptionValue;
 ///
-/// // Builds the project in the directory located in `libfoo`, installing it
-/// // into $OUT_DIR
-/// let dst = xmake::build("libfoo");
+/// assert_eq!(OptionValue::from(true).to_string(), "y");
+/// assert_eq!(OptionValue::from(4).to_string(), "4");
+/// assert_eq!(OptionValue::from(u64::MAX).to_string(), "18446744073709551615");
+/// assert_eq!(OptionValue::from("foo").to_string(), "foo");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    /// A boolean, passed as `y` or `n`.
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// An unsigned integer.
+    UInt(u64),
+    /// A string, passed as is.
+    Str(OsString),
+    /// A path.
+    Path(PathBuf),
+}
+
+impl OptionValue {
+    /// Returns the value passed to xmake.
+    pub fn into_os_string(self) -> OsString {
+        match self {
+            OptionValue::Str(s) => s,
+            OptionValue::Path(p) => p.into_os_string(),
+            value => OsString::from(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for OptionValue {
+    /// Formats the value, replacing the invalid UTF-8 of strings and paths.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Bool(true) => write!(f, "y"),
+            OptionValue::Bool(false) => write!(f, "n"),
+            OptionValue::Int(i) => write!(f, "{}", i),
+            OptionValue::UInt(i) => write!(f, "{}", i),
+            OptionValue::Str(s) => write!(f, "{}", s.to_string_lossy()),
+            OptionValue::Path(p) => write!(f, "{}", p.display()),
+        }
+    }
+}
+
+impl From<bool> for OptionValue {
+    fn from(value: bool) -> Self {
+        OptionValue::Bool(value)
+    }
+}
+
+macro_rules! option_value_from {
+    ($variant:ident, $target:ty, $($t:ty),*) => {
+        $(
+            impl From<$t> for OptionValue {
+                fn from(value: $t) -> Self {
+                    // Lossless: Rust doesn't support pointers wider than 64 bits
+                    OptionValue::$variant(<$target>::try_from(value).unwrap())
+                }
+            }
+        )*
+    };
+}
+
+option_value_from!(Int, i64, i8, i16, i32, i64, isize);
+option_value_from!(UInt, u64, u8, u16, u32, u64, usize);
+
+macro_rules! option_value_from_os {
+    ($variant:ident, $($t:ty),*) => {
+        $(
+            impl From<$t> for OptionValue {
+                fn from(value: $t) -> Self {
+                    OptionValue::$variant(AsRef::<OsStr>::as_ref(&value).to_owned())
+                }
+            }
+        )*
+    };
+}
+
+option_value_from_os!(Str, &str, &String, &OsStr, &OsString);
+option_value_from_os!(Str, &Cow<'_, OsStr>, Cow<'_, OsStr>);
+
+impl From<String> for OptionValue {
+    fn from(value: String) -> Self {
+        OptionValue::Str(value.into())
+    }
+}
+
+impl From<OsString> for OptionValue {
+    fn from(value: OsString) -> Self {
+        OptionValue::Str(value)
+    }
+}
+
+impl From<Cow<'_, str>> for OptionValue {
+    fn from(value: Cow<'_, str>) -> Self {
+        OptionValue::Str(value.into_owned().into())
+    }
+}
+
+impl From<&Path> for OptionValue {
+    fn from(value: &Path) -> Self {
+        OptionValue::Path(value.to_path_buf())
+    }
+}
+
+impl From<&PathBuf> for OptionValue {
+    fn from(value: &PathBuf) -> Self {
+        OptionValue::Path(value.clone())
+    }
+}
+
+impl From<PathBuf> for OptionValue {
+    fn from(value: PathBuf) -> Self {
+        OptionValue::Path(value)
+    }
+}
+
+impl From<Cow<'_, Path>> for OptionValue {
+    fn from(value: Cow<'_, Path>) -> Self {
+        OptionValue::Path(value.into_owned())
+    }
+}
+
+/// Errors that can occur while parsing the information reported by xmake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsingError {
+    /// A link kind is not one of the known kinds.
+    InvalidKind,
+    /// The required key named by the value is missing.
+    MissingKey(String),
+    /// A key expecting a single value got several.
+    MultipleValues,
+    /// A value couldn't be parsed.
+    ParseError,
+}
+
+impl fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsingError::InvalidKind => write!(f, "invalid link kind"),
+            ParsingError::MissingKey(key) => write!(f, "missing key `{}`", key),
+            ParsingError::MultipleValues => write!(f, "multiple values for a single key"),
+            ParsingError::ParseError => write!(f, "couldn't parse value"),
+        }
+    }
+}
+
+impl std::error::Error for ParsingError {}
+
+/// Information about the build reported by xmake once the project is built,
+/// used to link the produced libraries.
+///
+/// It is parsed from the output of the `build_info.lua` script, ignoring what
+/// xmake prints around the markers, like its first-run messages.
+///
+/// # Examples
 ///
-/// println!("cargo:rustc-link-search=native={}", dst.display());
-/// println!("cargo:rustc-link-lib=static=foo");
 /// ```
+/// use xmake::BuildInfo;
 ///
-pub fn build<P: AsRef<Path>>(path: P) -> PathBuf {
-    Config::new(path.as_ref()).build()
+/// let output = "downloading xmake-core.. ok\n__xmakers_start__
+/// linkdirs:/lib
+/// frameworkdirs:
+/// links:foo/static|bar/shared
+/// syslinks:pthread
+/// packages:
+/// cxx_used:false
+/// stl_used:false
+/// __xmakers_end__";
+/// let info: BuildInfo = output.parse().unwrap();
+/// assert_eq!(info.links().len(), 2);
+/// assert_eq!(info.syslinks(), ["pthread"]);
+/// ```
+///
+/// The link directories and the links are deduplicated, keeping their first
+/// occurrence. A library linked with different kinds is kept for each kind,
+/// and the include directories of a target reported twice are merged.
+///
+/// ```
+/// use std::path::PathBuf;
+/// use xmake::{BuildInfo, LinkKind, Source};
+///
+/// let output = "__xmakers_start__
+/// linkdirs:/b|/a|/b
+/// frameworkdirs:
+/// links:z/static|foo/static|z/static|z/shared
+/// includedirs_target.foo:/inc1
+/// includedirs_target.foo:/inc2|/inc1
+/// syslinks:
+/// packages:
+/// cxx_used:false
+/// stl_used:false
+/// __xmakers_end__";
+/// let info: BuildInfo = output.parse().unwrap();
+/// assert_eq!(info.linkdirs(), [PathBuf::from("/b"), PathBuf::from("/a")]);
+/// assert_eq!(
+///     info.includedirs(Source::Target, "foo"),
+///     [PathBuf::from("/inc1"), PathBuf::from("/inc2")]
+/// );
+/// let links: Vec<_> = info.links().iter().map(|l| (l.name(), l.kind())).collect();
+/// assert_eq!(
+///     links,
+///     [
+///         ("z", LinkKind::Static),
+///         ("foo", LinkKind::Static),
+///         ("z", LinkKind::Dynamic)
+///     ]
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildInfo {
+    linkdirs: Vec<PathBuf>,
+    frameworkdirs: Vec<PathBuf>,
+    links: Vec<Link>,
+    syslinks: Vec<String>,
+    packages: Vec<Package>,
+    includedirs_package: HashMap<String, Vec<PathBuf>>,
+    includedirs_target: HashMap<String, Vec<PathBuf>>,
+    use_cxx: bool,
+    use_stl: bool,
+    compile_commands: Option<PathBuf>,
+    /// The installed debug symbols come first, see `installed_debug_symbols`.
+    debug_symbols: Vec<PathBuf>,
+    installed_debug_symbols: usize,
+    libdir: Option<PathBuf>,
 }
 
-impl Config {
-    /// Creates a new blank set of configuration to build the project specified
-    /// at the path `path`.
-    pub fn new<P: AsRef<Path>>(path: P) -> Config {
-        Config {
-            path: env::current_dir().unwrap().join(path),
-            target: None,
-            verbose: false,
-            out_dir: None,
-            mode: None,
-            options: Vec::new(),
-            env: Vec::new(),
-            static_crt: None,
-            cpp_link_stdlib: None,
-            env_cache: HashMap::new(),
+impl BuildInfo {
+    /// Returns the directories in which the libraries can be found.
+    pub fn linkdirs(&self) -> &[PathBuf] {
+        &self.linkdirs
+    }
+
+    /// Returns the directories in which the frameworks can be found, only
+    /// used on Apple platforms.
+    pub fn frameworkdirs(&self) -> &[PathBuf] {
+        &self.frameworkdirs
+    }
+
+    /// Returns the libraries to link against, in the order reported by xmake.
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Returns the libraries coming from the target or package `name`, in
+    /// the order reported by xmake.
+    ///
+    /// The name `*` selects every target or package of the given source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmake::{BuildInfo, LinkOrigin, Source};
+    ///
+    /// let output = "__xmakers_start__
+    /// linkdirs:
+    /// frameworkdirs:
+    /// links:foo/static|crypto/static
+    /// links_target.foo:foo/static
+    /// links_package.openssl:crypto/static
+    /// syslinks:
+    /// packages:openssl/3.0.0
+    /// cxx_used:false
+    /// stl_used:false
+    /// __xmakers_end__";
+    /// let info: BuildInfo = output.parse().unwrap();
+    /// let links = info.links_from(Source::Package, "openssl");
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].name(), "crypto");
+    /// assert_eq!(links[0].origin(), &LinkOrigin::Package("openssl".to_string()));
+    /// ```
+    pub fn links_from<S: AsRef<str>>(&self, source: Source, name: S) -> Vec<Link> {
+        let name = name.as_ref();
+        self.links
+            .iter()
+            .filter(|link| match (source, link.origin()) {
+                (Source::Target | Source::Both, LinkOrigin::Target(n))
+                | (Source::Package | Source::Both, LinkOrigin::Package(n)) => {
+                    name == "*" || n == name
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the system libraries required by the targets and packages,
+    /// declared with `add_syslinks`.
+    pub fn syslinks(&self) -> &[String] {
+        &self.syslinks
+    }
+
+    /// Returns the packages resolved by xmake for the built targets.
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// Returns the include directories of the target or package `name`.
+    ///
+    /// The name `*` selects every target or package of the given source,
+    /// sorted by name. A directory is only returned once.
+    pub fn includedirs<S: AsRef<str>>(&self, source: Source, name: S) -> Vec<PathBuf> {
+        self.includedirs_iter(source, name.as_ref())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Returns the include directories of the target or package `name`,
+    /// without cloning them.
+    ///
+    /// The name `*` selects every target or package of the given source,
+    /// sorted by name. A directory is only returned once, even if it belongs
+    /// to both a target and a package.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use xmake::{BuildInfo, Source};
+    ///
+    /// let output = "__xmakers_start__
+    /// linkdirs:
+    /// frameworkdirs:
+    /// links:
+    /// syslinks:
+    /// packages:
+    /// includedirs_target.foo:/foo|/shared
+    /// includedirs_package.zlib:/zlib|/shared
+    /// includedirs_package.bar:/bar
+    /// cxx_used:false
+    /// stl_used:false
+    /// __xmakers_end__";
+    /// let info: BuildInfo = output.parse().unwrap();
+    /// let dirs: Vec<&Path> = info.includedirs_iter(Source::Both, "*").collect();
+    /// let expected = ["/foo", "/shared", "/bar", "/zlib"].map(Path::new);
+    /// assert_eq!(dirs, expected);
+    /// ```
+    pub fn includedirs_iter<'a>(
+        &'a self,
+        source: Source,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Path> + 'a {
+        let maps = match source {
+            Source::Target => [Some(&self.includedirs_target), None],
+            Source::Package => [Some(&self.includedirs_package), None],
+            Source::Both => [
+                Some(&self.includedirs_target),
+                Some(&self.includedirs_package),
+            ],
+        };
+
+        let mut seen = HashSet::new();
+        maps.into_iter()
+            .flatten()
+            .flat_map(move |map| {
+                sorted_keys(map)
+                    .filter(move |key| name == "*" || *key == name)
+                    .flat_map(move |key| map[key].iter())
+            })
+            .map(PathBuf::as_path)
+            .filter(move |dir| seen.insert(*dir))
+    }
+
+    /// Returns the names of the targets having include directories, sorted.
+    pub fn target_names(&self) -> impl Iterator<Item = &str> {
+        sorted_keys(&self.includedirs_target)
+    }
+
+    /// Returns the names of the packages having include directories, sorted.
+    pub fn package_names(&self) -> impl Iterator<Item = &str> {
+        sorted_keys(&self.includedirs_package)
+    }
+
+    /// Returns the include directories of every target and then every
+    /// package, without duplicates.
+    ///
+    /// The targets and packages are sorted by name so that the order is the
+    /// same between builds.
+    pub fn all_includedirs(&self) -> Vec<PathBuf> {
+        self.includedirs(Source::Both, "*")
+    }
+
+    /// Returns the `-I<dir>` arguments of [`BuildInfo::all_includedirs`], for
+    /// example to pass to `bindgen::Builder::clang_args`.
+    pub fn clang_include_args(&self) -> Vec<String> {
+        self.all_includedirs()
+            .iter()
+            .map(|dir| format!("-I{}", dir.display()))
+            .collect()
+    }
+
+    /// Returns the path of the generated `compile_commands.json`, if
+    /// [`Config::export_compile_commands`] was used.
+    pub fn compile_commands(&self) -> Option<&Path> {
+        self.compile_commands.as_deref()
+    }
+
+    /// Returns the separate debug symbols found in the build and install
+    /// directories, like the `.dwo` files of `-gsplit-dwarf`, the `.dSYM`
+    /// bundles on macOS or the `.pdb` files on Windows.
+    ///
+    /// The ones of [`BuildInfo::debug_files`] come first.
+    pub fn debug_symbol_paths(&self) -> &[PathBuf] {
+        &self.debug_symbols
+    }
+
+    /// Returns the debug symbols installed next to the libraries, for the
+    /// `debug` and `releasedbg` modes.
+    ///
+    /// These are the `.pdb` files on Windows, the `.dSYM` bundles on macOS
+    /// and the split DWARF files, when produced by the build.
+    pub fn debug_files(&self) -> &[PathBuf] {
+        let installed = self.installed_debug_symbols.min(self.debug_symbols.len());
+        &self.debug_symbols[..installed]
+    }
+
+    /// Returns whether one of the installed libraries defines `symbol`.
+    ///
+    /// The libraries are inspected with `dumpbin` on Windows and `nm`
+    /// otherwise, the symbols being looked up with and without the leading
+    /// underscore of Apple platforms. Returns `false` if the tool can't be run.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut config = xmake::Config::new("libfoo");
+    /// config.build();
+    /// assert!(config.build_info().defines_symbol("foo_init"));
+    /// ```
+    pub fn defines_symbol(&self, symbol: &str) -> bool {
+        let Some(Ok(entries)) = self.libdir.as_ref().map(fs::read_dir) else {
+            return false;
+        };
+        let prefixed = format!("_{}", symbol);
+        entries.flatten().any(|entry| {
+            let path = entry.path();
+            let ext = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+            let output = match ext {
+                "lib" | "dll" if cfg!(windows) => {
+                    let flag = if ext == "dll" { "/EXPORTS" } else { "/SYMBOLS" };
+                    Command::new("dumpbin").arg(flag).arg(&path).output()
+                }
+                "a" | "so" | "dylib" => Command::new("nm")
+                    .args(["-g", "--defined-only"])
+                    .arg(&path)
+                    .output(),
+                _ => return false,
+            };
+            let Ok(output) = output else {
+                return false;
+            };
+            String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+                line.split_whitespace()
+                    .last()
+                    .is_some_and(|name| name == symbol || name == prefixed)
+            })
+        })
+    }
+
+    /// Returns whether C++ is used by the built targets.
+    pub fn use_cxx(&self) -> bool {
+        self.use_cxx
+    }
+
+    /// Returns whether the C++ standard library must be linked.
+    pub fn use_stl(&self) -> bool {
+        self.use_stl
+    }
+
+    /// Writes the information to the file `path`, to reload it with
+    /// [`BuildInfo::load`] without building the project again, for example
+    /// in a later stage of a CI pipeline.
+    ///
+    /// The file holds the `key:value1|value2` lines of [`fmt::Display`]. This
+    /// fails with [`ErrorKind::InvalidInput`] if they can't represent the
+    /// information, like a path that isn't valid UTF-8 or contains a `|`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let content = self.to_string();
+        if content.parse().as_ref() != Ok(self) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the build information can't be written as key:value lines",
+            ));
+        }
+        fs::write(path, content)
+    }
+
+    /// Reads the information written to the file `path` by
+    /// [`BuildInfo::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<BuildInfo> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Serializes the information to JSON, to reload it with
+    /// [`BuildInfo::from_json`].
+    ///
+    /// This fails if a path isn't valid UTF-8.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Deserializes the information serialized by [`BuildInfo::to_json`].
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<BuildInfo, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    /// Formats the information as the `key:value1|value2` lines parsed by
+    /// [`FromStr`], including the fields set by [`Config::build`] after the
+    /// parsing, like the library directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xmake::BuildInfo;
+    ///
+    /// let output = "__xmakers_start__
+    /// linkdirs:/lib
+    /// frameworkdirs:
+    /// links:foo/static|crypto/shared
+    /// links_package.openssl:crypto/shared
+    /// syslinks:pthread
+    /// packages:openssl/3.0.0
+    /// includedirs_package.openssl:/openssl/include
+    /// cxx_used:true
+    /// stl_used:true
+    /// __xmakers_end__";
+    /// let info: BuildInfo = output.parse().unwrap();
+    /// assert_eq!(info.to_string().parse::<BuildInfo>(), Ok(info));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn line<T: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            key: &str,
+            values: impl IntoIterator<Item = T>,
+        ) -> fmt::Result {
+            let values: Vec<String> = values.into_iter().map(|v| v.to_string()).collect();
+            writeln!(f, "{}:{}", key, values.join("|"))
+        }
+        fn paths<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<String> {
+            paths.into_iter().map(|p| p.display().to_string()).collect()
+        }
+
+        line(f, "linkdirs", paths(&self.linkdirs))?;
+        line(f, "frameworkdirs", paths(&self.frameworkdirs))?;
+        line(f, "links", &self.links)?;
+        let mut origins: Vec<&LinkOrigin> = Vec::new();
+        for link in self.links.iter() {
+            if link.origin != LinkOrigin::Unknown && !origins.contains(&&link.origin) {
+                origins.push(&link.origin);
+            }
+        }
+        for origin in origins {
+            let key = match origin {
+                LinkOrigin::Target(name) => format!("links_target.{}", name),
+                LinkOrigin::Package(name) => format!("links_package.{}", name),
+                LinkOrigin::Unknown => unreachable!(),
+            };
+            line(f, &key, self.links.iter().filter(|l| &l.origin == origin))?;
         }
+        line(f, "syslinks", &self.syslinks)?;
+        line(f, "packages", &self.packages)?;
+        for name in sorted_keys(&self.includedirs_target) {
+            let key = format!("includedirs_target.{}", name);
+            line(f, &key, paths(&self.includedirs_target[name]))?;
+        }
+        for name in sorted_keys(&self.includedirs_package) {
+            let key = format!("includedirs_package.{}", name);
+            line(f, &key, paths(&self.includedirs_package[name]))?;
+        }
+        line(f, "cxx_used", [self.use_cxx])?;
+        line(f, "stl_used", [self.use_stl])?;
+        line(f, "compile_commands", paths(&self.compile_commands))?;
+        line(f, "debug_symbols", paths(&self.debug_symbols))?;
+        line(f, "installed_debug_symbols", [self.installed_debug_symbols])?;
+        line(f, "libdir", paths(&self.libdir))
+    }
+}
+
+impl FromStr for BuildInfo {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let map = parse_info_pairs(s);
+
+        // The values are deduplicated, keeping their first occurrence
+        let mut seen = HashSet::new();
+        let linkdirs = get_values(&map, "linkdirs")?
+            .iter()
+            .filter(|dir| seen.insert(dir.as_str()))
+            .map(PathBuf::from)
+            .collect();
+        let mut seen = HashSet::new();
+        let frameworkdirs = get_values(&map, "frameworkdirs")?
+            .iter()
+            .filter(|dir| seen.insert(dir.as_str()))
+            .map(PathBuf::from)
+            .collect();
+        let mut links = get_values(&map, "links")?
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Link>, _>>()?;
+
+        // A library reported by several targets or packages is attributed to
+        // the first of them, the targets coming first.
+        let mut origins: Vec<_> = map
+            .iter()
+            .filter_map(|(key, values)| {
+                let origin = match key.split_once('.')? {
+                    ("links_target", name) => (0, name, LinkOrigin::Target(name.to_string())),
+                    ("links_package", name) => (1, name, LinkOrigin::Package(name.to_string())),
+                    _ => return None,
+                };
+                Some((origin, values))
+            })
+            .collect();
+        origins.sort_by_key(|((order, name, _), _)| (*order, *name));
+        for (link, raw) in links.iter_mut().zip(get_values(&map, "links")?) {
+            if let Some(((_, _, origin), _)) =
+                origins.iter().find(|(_, values)| values.contains(raw))
+            {
+                link.origin = origin.clone();
+            }
+        }
+        let mut seen = HashSet::new();
+        links.retain(|link| seen.insert((link.name.clone(), link.kind)));
+        let syslinks = get_values(&map, "syslinks")?.clone();
+        let packages = get_values(&map, "packages")?
+            .iter()
+            .map(|p| p.parse())
+            .collect::<Result<Vec<Package>, _>>()?;
+
+        let mut includedirs_package = HashMap::new();
+        let mut includedirs_target = HashMap::new();
+        for (key, values) in map.iter() {
+            let mut seen = HashSet::new();
+            let dirs = values
+                .iter()
+                .filter(|dir| seen.insert(dir.as_str()))
+                .map(PathBuf::from)
+                .collect();
+            if let Some(name) = key.strip_prefix("includedirs_package.") {
+                includedirs_package.insert(name.to_string(), dirs);
+            } else if let Some(name) = key.strip_prefix("includedirs_target.") {
+                includedirs_target.insert(name.to_string(), dirs);
+            }
+        }
+
+        Ok(BuildInfo {
+            linkdirs,
+            frameworkdirs,
+            links,
+            syslinks,
+            packages,
+            includedirs_package,
+            includedirs_target,
+            use_cxx: parse_field(&map, "cxx_used")?,
+            use_stl: parse_field(&map, "stl_used")?,
+            // Only written by `BuildInfo::save`, not by the lua script
+            compile_commands: optional_path(&map, "compile_commands"),
+            debug_symbols: map
+                .get("debug_symbols")
+                .map_or(Vec::new(), |v| v.iter().map(PathBuf::from).collect()),
+            installed_debug_symbols: match map.contains_key("installed_debug_symbols") {
+                true => parse_field(&map, "installed_debug_symbols")?,
+                false => 0,
+            },
+            libdir: optional_path(&map, "libdir"),
+        })
+    }
+}
+
+impl BuildInfo {
+    /// Emits the cargo directives to link the libraries, like
+    /// [`Config::build`] does unless [`Config::auto_link`] is disabled.
+    ///
+    /// This lets a build script emit its own directives before or after the
+    /// ones of the libraries. The libraries are searched in the install
+    /// directory of the build, then in the link directories reported by xmake
+    /// and the search paths of `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::{LinkOptions, XmakeSession};
+    ///
+    /// let mut config = xmake::Config::new("libfoo");
+    /// config.auto_link(false).build();
+    ///
+    /// println!("cargo:rustc-link-lib=static=bar");
+    /// let plat = XmakeSession::new().plat().unwrap().to_string();
+    /// config.build_info().emit_link_directives(&LinkOptions::new(&plat));
+    /// ```
+    pub fn emit_link_directives(&self, options: &LinkOptions) {
+        for directive in self.link_directives(options) {
+            println!("cargo:{}", directive);
+        }
+    }
+
+    /// Returns the cargo directives printed by
+    /// [`BuildInfo::emit_link_directives`], without the `cargo:` prefix.
+    fn link_directives(&self, options: &LinkOptions) -> Vec<String> {
+        let (mut directives, links) = self.collect_links(options);
+        directives.extend(self.lib_directives(&links, options));
+        directives
+    }
+
+    /// Returns the directives of the link search paths and of the warnings
+    /// about the libraries, along with the libraries to link in the order
+    /// they must be linked.
+    fn collect_links(&self, options: &LinkOptions) -> (Vec<String>, OrderedSet<Link>) {
+        let mut directives = Vec::new();
+        let plat = options.plat.as_str();
+        let dst = options.libdir(self);
+
+        let mut linkdirs = OrderedSet::new();
+        linkdirs.insert(dst.as_path());
+        for linkdir in self.linkdirs().iter().chain(options.search_paths.iter()) {
+            linkdirs.insert(linkdir.as_path());
+        }
+        for linkdir in linkdirs.items {
+            directives.push(format!("rustc-link-search=all={}", linkdir.display()));
+        }
+        if matches!(plat, "macosx" | "iphoneos" | "appletvos" | "watchos") {
+            for frameworkdir in self.frameworkdirs() {
+                directives.push(format!(
+                    "rustc-link-search=framework={}",
+                    frameworkdir.display()
+                ));
+            }
+        }
+
+        // A library can be reported by several targets or packages, only keep
+        // its first occurrence regardless of its origin, so the relative order
+        // of the libraries is kept.
+        let mut links = OrderedSet::new();
+        for link in self.links() {
+            links.insert(Link::new(link.name(), link.kind()));
+        }
+        for (i, link) in links.items.iter().enumerate() {
+            let other = links.items[..i].iter().find(|l| l.name() == link.name());
+            if let Some(other) = other {
+                directives.push(format!(
+                    "warning=the library `{}` is linked both as {} and {}",
+                    link.name(),
+                    other.kind(),
+                    link.kind()
+                ));
+            }
+        }
+
+        if self.use_stl() && !options.no_stl_link {
+            if let Some(stl) = get_stl(plat, options.cpp_link_stdlib.as_deref()) {
+                links.insert(stl);
+            }
+
+            // libstdc++ depends on these system libraries, which may not be
+            // reported by xmake when linked statically.
+            if plat == "linux" && options.link_stl_deps {
+                for name in ["pthread", "dl", "m"] {
+                    links.insert(Link::new(name, LinkKind::System));
+                }
+            }
+        }
+
+        // The system libraries come last, as the other libraries depend on them.
+        for syslink in self.syslinks() {
+            if let Some(name) = get_syslink(plat, syslink) {
+                links.insert(Link::new(name, LinkKind::System));
+            }
+        }
+
+        // xmake doesn't report the shared libraries installed by the packages
+        // on Linux, so look for them in the install directory, unless disabled
+        // with `Config::scan_shared_libs`. They are linked by name after the
+        // reported libraries, in the same order on every file system.
+        if plat == "linux" && options.scan_shared_libs {
+            let mut names: Vec<_> = fs::read_dir(&dst)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_string_lossy().into_owned();
+                    shared_lib_name(&file_name).map(str::to_string)
+                })
+                .collect();
+            // The order of read_dir depends on the file system
+            names.sort();
+            for name in names {
+                // Don't link again a library already reported by xmake
+                if !links.items.iter().any(|l| l.name() == name) {
+                    links.insert(Link::new(&name, LinkKind::Dynamic));
+                }
+            }
+        }
+        (directives, links)
+    }
+
+    /// Returns the directives linking `links`, followed by the linker
+    /// arguments.
+    fn lib_directives(&self, links: &OrderedSet<Link>, options: &LinkOptions) -> Vec<String> {
+        let plat = options.plat.as_str();
+        let mut directives = Vec::new();
+        for link in links.items.iter() {
+            let system = matches!(link.kind(), LinkKind::System | LinkKind::Framework);
+            if (system && !options.emit_system_libs) || link.kind() == LinkKind::HeaderOnly {
+                continue;
+            }
+            let whole_archive = options.whole_archive_all
+                || options.whole_archives.iter().any(|n| n == link.name());
+            let kind = match link.kind() {
+                // Linking modifiers are stable since Rust 1.61
+                LinkKind::Static if whole_archive => "static:+whole-archive,-bundle=",
+                LinkKind::Static => "static=",
+                LinkKind::Dynamic => "dylib=",
+                LinkKind::Framework => "framework=",
+                LinkKind::System | LinkKind::Unknown => "",
+                LinkKind::HeaderOnly => unreachable!(),
+            };
+            directives.push(format!("rustc-link-lib={}{}", kind, link.name()));
+        }
+
+        if !matches!(plat, "windows" | "mingw" | "wasm") {
+            let apple = matches!(plat, "macosx" | "iphoneos" | "appletvos" | "watchos");
+            match options.rpath {
+                RpathMode::None => {}
+                RpathMode::InstallDir => {
+                    let dst = options.libdir(self);
+                    directives.push(format!("rustc-link-arg=-Wl,-rpath,{}", dst.display()))
+                }
+                RpathMode::Origin if apple => {
+                    directives.push("rustc-link-arg=-Wl,-rpath,@loader_path".to_string())
+                }
+                RpathMode::Origin => {
+                    directives.push("rustc-link-arg=-Wl,-rpath,$ORIGIN".to_string())
+                }
+            }
+        }
+
+        // The instrumented code needs the runtime of the sanitizers, which
+        // MSVC links on its own.
+        if !options.sanitizers.is_empty() && plat != "windows" {
+            let names: Vec<_> = options.sanitizers.iter().map(Sanitizer::name).collect();
+            directives.push(format!("rustc-link-arg=-fsanitize={}", names.join(",")));
+        }
+
+        for arg in options.link_args.iter() {
+            directives.push(format!("rustc-link-arg={}", arg));
+        }
+        directives
+    }
+}
+
+/// Options of [`BuildInfo::emit_link_directives`], which mirror the link
+/// options of [`Config`].
+#[derive(Debug, Clone)]
+pub struct LinkOptions {
+    plat: String,
+    libdir: Option<PathBuf>,
+    search_paths: Vec<PathBuf>,
+    no_stl_link: bool,
+    cpp_link_stdlib: Option<String>,
+    link_stl_deps: bool,
+    emit_system_libs: bool,
+    scan_shared_libs: bool,
+    whole_archives: Vec<String>,
+    whole_archive_all: bool,
+    rpath: RpathMode,
+    sanitizers: Vec<Sanitizer>,
+    link_args: Vec<String>,
+}
+
+impl LinkOptions {
+    /// Creates the default options for the xmake platform `plat`, as
+    /// returned by [`XmakeSession::plat`].
+    pub fn new(plat: &str) -> LinkOptions {
+        LinkOptions {
+            plat: plat.to_string(),
+            libdir: None,
+            search_paths: Vec::new(),
+            no_stl_link: false,
+            cpp_link_stdlib: None,
+            link_stl_deps: true,
+            emit_system_libs: true,
+            scan_shared_libs: true,
+            whole_archives: Vec::new(),
+            whole_archive_all: false,
+            rpath: RpathMode::None,
+            sanitizers: Vec::new(),
+            link_args: Vec::new(),
+        }
+    }
+
+    /// Adds a directory in which the libraries are searched, after the ones
+    /// of the build.
+    pub fn search_path<P: AsRef<Path>>(&mut self, dir: P) -> &mut LinkOptions {
+        self.search_paths.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets whether linking the C++ standard library is skipped, to link it
+    /// manually.
+    /// This option defaults to `false`.
+    pub fn no_stl_link(&mut self, value: bool) -> &mut LinkOptions {
+        self.no_stl_link = value;
+        self
+    }
+
+    /// Sets the C++ standard library linked on Android, see
+    /// [`Config::cpp_link_stdlib`].
+    pub fn cpp_link_stdlib(&mut self, stdlib: &str) -> &mut LinkOptions {
+        self.cpp_link_stdlib = Some(stdlib.to_string());
+        self
+    }
+
+    /// See [`Config::link_stl_deps`].
+    pub fn link_stl_deps(&mut self, value: bool) -> &mut LinkOptions {
+        self.link_stl_deps = value;
+        self
+    }
+
+    /// See [`Config::emit_system_libs`].
+    pub fn emit_system_libs(&mut self, value: bool) -> &mut LinkOptions {
+        self.emit_system_libs = value;
+        self
+    }
+
+    /// See [`Config::scan_shared_libs`].
+    pub fn scan_shared_libs(&mut self, value: bool) -> &mut LinkOptions {
+        self.scan_shared_libs = value;
+        self
+    }
+
+    /// See [`Config::whole_archive`].
+    pub fn whole_archive(&mut self, name: &str) -> &mut LinkOptions {
+        self.whole_archives.push(name.to_string());
+        self
+    }
+
+    /// See [`Config::whole_archive_all`].
+    pub fn whole_archive_all(&mut self, value: bool) -> &mut LinkOptions {
+        self.whole_archive_all = value;
+        self
+    }
+
+    /// See [`Config::rpath`].
+    pub fn rpath(&mut self, mode: RpathMode) -> &mut LinkOptions {
+        self.rpath = mode;
+        self
+    }
+
+    /// Links the runtime of the sanitizer `kind`, see [`Config::sanitizer`].
+    pub fn sanitizer(&mut self, kind: Sanitizer) -> &mut LinkOptions {
+        if !self.sanitizers.contains(&kind) {
+            self.sanitizers.push(kind);
+        }
+        self
+    }
+
+    /// See [`Config::rustc_link_arg`].
+    pub fn link_arg<S: AsRef<str>>(&mut self, arg: S) -> &mut LinkOptions {
+        self.link_args.push(arg.as_ref().to_string());
+        self
+    }
+
+    /// Returns the directory in which the libraries were installed.
+    fn libdir(&self, info: &BuildInfo) -> PathBuf {
+        self.libdir
+            .clone()
+            .or_else(|| info.libdir.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the paths in the install directory `dst` of the artifacts printed
+/// by `artifacts.lua`, the libraries being installed in `dst/libdir`.
+fn artifact_paths(output: &str, dst: &Path, libdir: &str) -> Vec<PathBuf> {
+    parse_info_pairs(output)
+        .remove("artifacts")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|artifact| match artifact.strip_prefix("lib/") {
+            Some(file) => dst.join(libdir).join(file),
+            None => dst.join(artifact),
+        })
+        .collect()
+}
+
+/// Returns the name of the shared library `file_name`, like `foo` for
+/// `libfoo.so` or the versioned `libfoo.so.1.2`.
+fn shared_lib_name(file_name: &str) -> Option<&str> {
+    let (name, version) = file_name.strip_prefix("lib")?.rsplit_once(".so")?;
+    let versioned = version
+        .strip_prefix('.')
+        .is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit() || c == '.'));
+    (!name.is_empty() && (version.is_empty() || versioned)).then_some(name)
+}
+
+/// Returns the C++ standard library to link against for the platform `plat`,
+/// `cpp_link_stdlib` being the one chosen for Android.
+fn get_stl(plat: &str, cpp_link_stdlib: Option<&str>) -> Option<Link> {
+    let name = match plat {
+        "linux" | "mingw" => "stdc++",
+        "macosx" | "iphoneos" | "appletvos" | "watchos" => "c++",
+        "android" => cpp_link_stdlib.unwrap_or("c++_shared"),
+        // The MSVC standard library is linked along with the CRT
+        _ => return None,
+    };
+    Some(Link::new(name, LinkKind::Unknown))
+}
+
+/// Marker printed by the lua scripts before their output.
+const START_MARKER: &str = "__xmakers_start__";
+/// Marker printed by the lua scripts after their output.
+const END_MARKER: &str = "__xmakers_end__";
+
+/// Parses the `key:value1|value2` lines printed by the lua scripts.
+///
+/// If `s` contains the markers, only the lines between them are parsed. The
+/// values of a key printed several times are merged.
+fn parse_info_pairs<S: AsRef<str>>(s: S) -> HashMap<String, Vec<String>> {
+    let mut s = s.as_ref();
+    if let Some((_, rest)) = s.split_once(START_MARKER) {
+        s = rest.split_once(END_MARKER).map_or(rest, |(inner, _)| inner);
+    }
+
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for line in s.lines() {
+        if let Some((key, values)) = split_single_colon(line.trim()) {
+            let values = values
+                .split('|')
+                .filter(|v| !v.is_empty())
+                .map(str::to_string);
+            map.entry(key.to_string()).or_default().extend(values);
+        }
+    }
+    map
+}
+
+/// Returns the values of `field`.
+fn get_values<'a>(
+    map: &'a HashMap<String, Vec<String>>,
+    field: &str,
+) -> Result<&'a Vec<String>, ParsingError> {
+    map.get(field)
+        .ok_or_else(|| ParsingError::MissingKey(field.to_string()))
+}
+
+/// Returns the path of the optional `field`.
+fn optional_path(map: &HashMap<String, Vec<String>>, field: &str) -> Option<PathBuf> {
+    map.get(field).and_then(|v| v.first()).map(PathBuf::from)
+}
+
+/// Parses the single value of `field`.
+fn parse_field<T: FromStr>(
+    map: &HashMap<String, Vec<String>>,
+    field: &str,
+) -> Result<T, ParsingError> {
+    let values = get_values(map, field)?;
+    match values.as_slice() {
+        [value] => value.parse().map_err(|_| ParsingError::ParseError),
+        [] => Err(ParsingError::ParseError),
+        _ => Err(ParsingError::MultipleValues),
+    }
+}
+
+/// Flags passed by xmake to the compilers and the linker, returned by
+/// [`Config::resolved_flags`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResolvedFlags {
+    cflags: Vec<String>,
+    cxxflags: Vec<String>,
+    ldflags: Vec<String>,
+}
+
+impl ResolvedFlags {
+    /// Returns the flags passed to the C compiler.
+    pub fn cflags(&self) -> &[String] {
+        &self.cflags
+    }
+
+    /// Returns the flags passed to the C++ compiler.
+    pub fn cxxflags(&self) -> &[String] {
+        &self.cxxflags
+    }
+
+    /// Returns the flags passed to the linker, for the shared libraries and
+    /// binaries.
+    pub fn ldflags(&self) -> &[String] {
+        &self.ldflags
+    }
+}
+
+/// Returns the keys of `map`, sorted.
+fn sorted_keys<V>(map: &HashMap<String, V>) -> impl Iterator<Item = &str> {
+    let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    keys.into_iter()
+}
+
+/// A set that remembers the order in which the items were first inserted.
+struct OrderedSet<T> {
+    seen: HashSet<T>,
+    items: Vec<T>,
+}
+
+impl<T: Eq + Hash + Clone> OrderedSet<T> {
+    fn new() -> Self {
+        OrderedSet {
+            seen: HashSet::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Inserts `item`, returning `false` if it was already present.
+    fn insert(&mut self, item: T) -> bool {
+        if self.seen.insert(item.clone()) {
+            self.items.push(item);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The version of xmake, as reported by `xmake --version`.
+///
+/// # Examples
+///
+/// ```
+/// use xmake::Version;
+///
+/// let version: Version = "2.9.9".parse().unwrap();
+/// assert!(version > Version::new(2, 9, 4));
+/// assert_eq!(version.to_string(), "2.9.9");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl Version {
+    /// Creates a new version `major.minor.patch`.
+    pub fn new(major: u32, minor: u32, patch: u32) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Returns the major version.
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Returns the minor version.
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+
+    /// Returns the patch version.
+    pub fn patch(&self) -> u32 {
+        self.patch
+    }
+
+    /// Parses the output of `xmake --version`, like `xmake v2.9.4+20240829`,
+    /// `xmake v2.9.5+dev.478972cd9` or `xmake v2.9.5+HEAD.0db4fe6`.
+    fn parse(output: &str) -> Option<Version> {
+        let start = output.find("xmake v")? + "xmake ".len();
+        let version = output[start..]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .next()?;
+        version.parse().ok()
+    }
+}
+
+impl FromStr for Version {
+    type Err = ParsingError;
+
+    /// Parses a version formatted as `major.minor.patch`, with an optional `v`
+    /// prefix and `+build` suffix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('v').unwrap_or(s);
+        let s = s.split('+').next().unwrap_or(s);
+
+        let parts = s
+            .split('.')
+            .map(|p| p.parse::<u32>().map_err(|_| ParsingError::ParseError))
+            .collect::<Result<Vec<_>, _>>()?;
+        match parts.as_slice() {
+            [major, minor, patch] => Ok(Version::new(*major, *minor, *patch)),
+            _ => Err(ParsingError::ParseError),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Returns the path of the xmake executable, or `None` if it couldn't be found.
+///
+/// The executable is taken from the `XMAKE` environment variable if set, then
+/// looked for in `PATH` and in the usual install locations of xmake, like
+/// `%LOCALAPPDATA%\xmake` on Windows or `~/.local/bin` on Unix, which may be
+/// missing from the environment of the build scripts.
+///
+/// # Examples
+///
+/// ```no_run
+/// if xmake::find_executable().is_none() {
+///     println!("cargo:warning=xmake not found, using the prebuilt library");
+/// }
+/// ```
+pub fn find_executable() -> Option<PathBuf> {
+    if let Some(xmake) = env::var_os("XMAKE") {
+        return Some(PathBuf::from(split_program(&xmake).0));
+    }
+    search_executable(env::var_os("PATH"), &install_locations())
+}
+
+/// Splits the value of the `XMAKE` environment variable into the program to
+/// run and its arguments, for wrappers like `ccache xmake`.
+///
+/// A path to an existing file is kept as is even if it contains spaces,
+/// otherwise the value is split like a shell would.
+fn split_program(value: &OsStr) -> (OsString, Vec<OsString>) {
+    if Path::new(value).is_file() {
+        return (value.to_owned(), Vec::new());
+    }
+    match value.to_str().and_then(shlex::split) {
+        Some(parts) if !parts.is_empty() => {
+            let mut parts = parts.into_iter().map(OsString::from);
+            (parts.next().unwrap(), parts.collect())
+        }
+        _ => (value.to_owned(), Vec::new()),
+    }
+}
+
+/// Looks for xmake in the directories of `path`, then at the paths of
+/// `locations`.
+fn search_executable(path: Option<OsString>, locations: &[PathBuf]) -> Option<PathBuf> {
+    // Some Windows setups only provide a batch wrapper, which `Command` doesn't
+    // look for in PATH. The standard library runs them through `cmd /c` and
+    // quotes the arguments for it.
+    let names: &[&str] = if cfg!(windows) {
+        &["xmake.exe", "xmake.bat", "xmake.cmd"]
+    } else {
+        &["xmake"]
+    };
+    let dirs = path.as_deref().map(env::split_paths).into_iter().flatten();
+    dirs.flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .chain(locations.iter().cloned())
+        .find(|path| path.is_file())
+}
+
+/// Returns the usual install locations of xmake on the host.
+fn install_locations() -> Vec<PathBuf> {
+    let var = |v: &str| env::var_os(v).map(PathBuf::from);
+    let mut locations = Vec::new();
+    if cfg!(windows) {
+        if let Some(dir) = var("LOCALAPPDATA") {
+            locations.push(dir.join("xmake").join("xmake.exe"));
+        }
+        if let Some(dir) = var("ProgramFiles") {
+            locations.push(dir.join("xmake").join("xmake.exe"));
+        }
+        if let Some(dir) = var("USERPROFILE") {
+            let shims = dir.join("scoop").join("shims");
+            locations.push(shims.join("xmake.exe"));
+            locations.push(shims.join("xmake.cmd"));
+        }
+        if let Some(dir) = var("ChocolateyInstall") {
+            locations.push(dir.join("bin").join("xmake.exe"));
+        }
+    } else {
+        if let Some(dir) = var("HOME") {
+            locations.push(dir.join(".local").join("bin").join("xmake"));
+        }
+        if cfg!(target_os = "macos") {
+            locations.push(PathBuf::from("/opt/homebrew/bin/xmake"));
+        }
+        locations.push(PathBuf::from("/usr/local/bin/xmake"));
+        locations.push(PathBuf::from("/usr/bin/xmake"));
+    }
+    locations
+}
+
+/// Returns the xmake executable to run and its arguments, falling back to
+/// `xmake` so that spawning it reports it as not found.
+fn xmake_program() -> (OsString, Vec<OsString>) {
+    if let Some(xmake) = env::var_os("XMAKE") {
+        return split_program(&xmake);
+    }
+    let program = search_executable(env::var_os("PATH"), &install_locations());
+    let program = program.map_or_else(|| OsString::from("xmake"), PathBuf::into_os_string);
+    (program, Vec::new())
+}
+
+/// Returns the version of the installed xmake, or `None` if it couldn't be run.
+///
+/// The executable is found with [`find_executable`].
+/// `xmake --version` is only run once per process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xmake::Version;
+///
+/// if xmake::version().map_or(false, |v| v >= Version::new(2, 9, 0)) {
+///     println!("cargo:rustc-cfg=xmake_2_9");
+/// }
+/// ```
+pub fn version() -> Option<Version> {
+    let (xmake, args) = xmake_program();
+    program_version(&xmake, &args)
+}
+
+/// Runs `program --version`, only once per process for each program.
+fn program_version(program: &OsStr, args: &[OsString]) -> Option<Version> {
+    type Versions = HashMap<(OsString, Vec<OsString>), Option<Version>>;
+    static VERSIONS: OnceLock<Mutex<Versions>> = OnceLock::new();
+    let key = (program.to_owned(), args.to_vec());
+    let versions = VERSIONS.get_or_init(Default::default);
+    if let Some(version) = versions.lock().unwrap().get(&key) {
+        return *version;
+    }
+    // Printing the version is harmless, so allow it even when running as root
+    let version = Command::new(program)
+        .args(args)
+        .arg("--version")
+        .env("XMAKE_ROOT", "y")
+        .output()
+        .ok()
+        .and_then(|output| Version::parse(&String::from_utf8_lossy(&output.stdout)));
+    versions.lock().unwrap().insert(key, version);
+    version
+}
+
+/// Returns the host triple of `rustc`, `$RUSTC` if set.
+fn rustc_host() -> Option<&'static str> {
+    static HOST: OnceLock<Option<String>> = OnceLock::new();
+    HOST.get_or_init(|| {
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc).arg("-vV").output().ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .map(|host| host.trim().to_string())
+    })
+    .as_deref()
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        if !self.clean_on_drop.0 {
+            return;
+        }
+        let Some(out_dir) = self.out_dir.as_ref() else {
+            return;
+        };
+        // Only remove a directory strictly inside the temporary directory
+        let temp_dir = env::temp_dir();
+        let cargo_out_dir = env::var_os("OUT_DIR").map(PathBuf::from);
+        if cargo_out_dir.as_ref() != Some(out_dir)
+            && out_dir.starts_with(&temp_dir)
+            && *out_dir != temp_dir
+        {
+            let _ = fs::remove_dir_all(out_dir);
+        }
+    }
+}
+
+/// Information resolved once and shared by several [`Config`], for build
+/// scripts building several independent projects.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xmake::XmakeSession;
+///
+/// let session = XmakeSession::new();
+/// session.config("libfoo").build();
+/// session.config("libbar").build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct XmakeSession {
+    version: Option<Version>,
+    plat: Option<String>,
+}
+
+impl XmakeSession {
+    /// Resolves the version of xmake and the xmake platform of the target.
+    pub fn new() -> XmakeSession {
+        XmakeSession {
+            version: version(),
+            plat: env::var("TARGET").ok().and_then(|target| {
+                get_xmake_plat(&Triple::from_cfg(&target, |v| env::var(v).ok()))
+            }),
+        }
+    }
+
+    /// Returns the version of xmake, or `None` if it couldn't be run.
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    /// Returns the xmake platform of the target, like `linux` or `macosx`.
+    pub fn plat(&self) -> Option<&str> {
+        self.plat.as_deref()
+    }
+
+    /// Creates a new configuration to build the project at `path`, reusing
+    /// the information resolved by the session.
+    pub fn config<P: AsRef<Path>>(&self, path: P) -> Config {
+        let mut config = Config::new(path);
+        config.cache.xmake_version = self.version;
+        config.cache.plat = self.plat.clone();
+        config
+    }
+}
+
+impl Default for XmakeSession {
+    fn default() -> Self {
+        XmakeSession::new()
+    }
+}
+
+/// Data computed during the build and reused across the different steps.
+#[derive(Default)]
+struct ConfigCache {
+    build_info: BuildInfo,
+    plat: Option<String>,
+    xmake_version: Option<Version>,
+    env: RefCell<HashMap<String, Option<OsString>>>,
+    rebuilt: bool,
+    installed_packages: Vec<String>,
+    project_watched: Cell<bool>,
+}
+
+impl ConfigCache {
+    /// Clears the data of the last build, keeping what only depends on the
+    /// host and the Rust target.
+    fn reset(&mut self) {
+        self.build_info = BuildInfo::default();
+        self.env.get_mut().clear();
+        self.rebuilt = false;
+        self.installed_packages.clear();
+    }
+}
+
+impl Clone for ConfigCache {
+    fn clone(&self) -> Self {
+        ConfigCache {
+            build_info: BuildInfo::default(),
+            plat: self.plat.clone(),
+            xmake_version: self.xmake_version,
+            env: RefCell::default(),
+            rebuilt: false,
+            installed_packages: Vec::new(),
+            project_watched: Cell::new(false),
+        }
+    }
+}
+
+/// A flag that isn't inherited by the clones of a [`Config`], for the options
+/// tied to the configuration that set them.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct OwnedFlag(bool);
+
+impl Clone for OwnedFlag {
+    fn clone(&self) -> Self {
+        OwnedFlag(false)
+    }
+}
+
+/// Builder style configuration for a pending XMake build.
+///
+/// A configuration can be cloned to build the same project with different
+/// options, the clone doesn't keep the information of the previous builds.
+#[derive(Clone)]
+pub struct Config {
+    path: PathBuf,
+    targets: Vec<String>,
+    verbosity: u8,
+    auto_link: bool,
+    out_dir: Option<PathBuf>,
+    mode: Option<String>,
+    options: Vec<(OsString, OsString)>,
+    env: Vec<(OsString, OsString)>,
+    env_remove: Vec<OsString>,
+    static_crt: Option<bool>,
+    cpp_link_stdlib: Option<String>,
+    target_kind: Option<LinkKind>,
+    always_configure: bool,
+    allow_root: Option<bool>,
+    prefer_system_packages: bool,
+    install_packages: bool,
+    minimum_version: Option<Version>,
+    offline: bool,
+    progress_interval: Duration,
+    repos: Vec<(String, String, Option<String>)>,
+    global_repo_dir: Option<PathBuf>,
+    compile_commands: Option<Option<PathBuf>>,
+    cancel: Option<CancelToken>,
+    incremental: bool,
+    install_libdir: String,
+    install_includedir: String,
+    package: Option<String>,
+    package_configs: Vec<(String, String)>,
+    requires: Vec<(String, Vec<(String, String)>)>,
+    universal_archs: Vec<String>,
+    build_arch: Option<String>,
+    lockfile: bool,
+    frozen: bool,
+    link_stl_deps: bool,
+    emit_system_libs: bool,
+    scan_shared_libs: bool,
+    sanitizers: Vec<Sanitizer>,
+    pic: bool,
+    bundle: Option<String>,
+    link_jobs: Option<usize>,
+    all_targets: bool,
+    group: Option<String>,
+    report_packages: bool,
+    profile_modes: Vec<(String, String)>,
+    jobs: Option<usize>,
+    toolchain: Option<String>,
+    sdk: Option<PathBuf>,
+    cross: Option<String>,
+    link_args: Vec<String>,
+    xmake_path: Option<PathBuf>,
+    target_options: Vec<(String, OsString, OsString)>,
+    rpath: RpathMode,
+    clean_on_drop: OwnedFlag,
+    strict_options: bool,
+    runtimes: Vec<String>,
+    build_dir: Option<PathBuf>,
+    shared_build_cache: bool,
+    exported_packages: Option<Vec<String>>,
+    whole_archives: Vec<String>,
+    whole_archive_all: bool,
+    locking: bool,
+    copy_dylibs: bool,
+    prefer_check_mode: bool,
+    wrapper_header: Option<PathBuf>,
+    verbose_commands: bool,
+    quiet: bool,
+    cargo_metadata: bool,
+    host: Option<String>,
+    target_triple: Option<String>,
+    cache: ConfigCache,
+}
+
+/// Builds the native library rooted at `path` with the default xmake options.
+/// This will return the directory in which the library was installed, the
+/// produced libraries are automatically linked.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xmake;
+///
+/// // Builds the project in the directory located in `libfoo`, installing it
+/// // into $OUT_DIR and linking the produced libraries
+/// let dst = xmake::build("libfoo");
+/// ```
+///
+pub fn build<P: AsRef<Path>>(path: P) -> PathBuf {
+    Config::new(path.as_ref()).build()
+}
+
+impl Config {
+    /// Creates a new blank set of configuration to build the project specified
+    /// at the path `path`.
+    ///
+    /// The build script is rerun when a file of the project directory changes.
+    /// Cargo can't exclude a directory from this watch, so the project must
+    /// not contain the `target` directory of cargo nor the
+    /// [`Config::build_dir`], keep it in a subdirectory of the crate instead
+    /// of the crate root.
+    pub fn new<P: AsRef<Path>>(path: P) -> Config {
+        Config {
+            path: env::current_dir().unwrap().join(path),
+            targets: Vec::new(),
+            verbosity: 0,
+            auto_link: true,
+            out_dir: None,
+            mode: None,
+            options: Vec::new(),
+            env: Vec::new(),
+            env_remove: Vec::new(),
+            static_crt: None,
+            cpp_link_stdlib: None,
+            target_kind: None,
+            always_configure: false,
+            allow_root: None,
+            prefer_system_packages: false,
+            install_packages: false,
+            minimum_version: None,
+            offline: false,
+            progress_interval: Duration::from_secs(60),
+            repos: Vec::new(),
+            global_repo_dir: None,
+            compile_commands: None,
+            cancel: None,
+            incremental: true,
+            install_libdir: "lib".to_string(),
+            install_includedir: "include".to_string(),
+            package: None,
+            package_configs: Vec::new(),
+            requires: Vec::new(),
+            universal_archs: Vec::new(),
+            build_arch: None,
+            lockfile: false,
+            frozen: false,
+            link_stl_deps: true,
+            emit_system_libs: true,
+            scan_shared_libs: true,
+            sanitizers: Vec::new(),
+            pic: false,
+            bundle: None,
+            link_jobs: None,
+            all_targets: false,
+            group: None,
+            report_packages: false,
+            profile_modes: Vec::new(),
+            jobs: None,
+            toolchain: None,
+            sdk: None,
+            cross: None,
+            link_args: Vec::new(),
+            xmake_path: None,
+            target_options: Vec::new(),
+            rpath: RpathMode::None,
+            clean_on_drop: OwnedFlag(false),
+            strict_options: false,
+            runtimes: Vec::new(),
+            build_dir: None,
+            shared_build_cache: false,
+            exported_packages: None,
+            whole_archives: Vec::new(),
+            whole_archive_all: false,
+            locking: true,
+            copy_dylibs: false,
+            prefer_check_mode: false,
+            wrapper_header: None,
+            verbose_commands: false,
+            quiet: false,
+            cargo_metadata: true,
+            host: None,
+            target_triple: None,
+            cache: ConfigCache::default(),
+        }
+    }
+
+    /// Creates a new configuration building the xrepo package `package`, like
+    /// `zlib` or `zlib 1.3`, without any xmake project.
+    ///
+    /// A minimal project requiring the package is generated in the output
+    /// directory, the package is then built and linked like a regular project.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let mut config = Config::from_package("zlib 1.3");
+    /// config.package_config("shared", "true").build();
+    /// let includedirs = config.build_info().includedirs(xmake::Source::Package, "zlib");
+    /// ```
+    pub fn from_package(package: &str) -> Config {
+        let mut config = Config::new(".");
+        config.package = Some(package.to_string());
+        config
+    }
+
+    /// Sets the config `key` of the package built by a configuration created
+    /// with [`Config::from_package`].
+    ///
+    /// `value` is a lua value, so strings must be quoted, like `"\"openssl\""`.
+    pub fn package_config(&mut self, key: &str, value: &str) -> &mut Config {
+        self.package_configs
+            .push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Requires the xrepo package `name` with the package configs `configs`,
+    /// like `add_requires(name, {configs = {...}})` would.
+    ///
+    /// The values are lua values, like for [`Config::package_config`].
+    /// The package is added to the project generated by
+    /// [`Config::from_package`]. For the other projects, it is installed once
+    /// configured with `xmake require --extra="{configs = {...}}"`, where the
+    /// `add_requires` of the project asking for the same configs find it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// Config::from_package("libcurl")
+    ///     .require_with_configs("openssl", &[("shared", "false")])
+    ///     .require_with_configs("boost", &[("filesystem", "true"), ("system", "true")])
+    ///     .build();
+    /// ```
+    pub fn require_with_configs(&mut self, name: &str, configs: &[(&str, &str)]) -> &mut Config {
+        let configs = configs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self.requires.push((name.to_string(), configs));
+        self
+    }
+
+    /// Sets the Rust target triple to build for, like `aarch64-linux-android`,
+    /// instead of `$TARGET` and the `$CARGO_CFG_TARGET_*` variables.
+    ///
+    /// The xmake platform and architecture are deduced from the triple.
+    /// [`Config::target`] sets the xmake targets to build instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// Config::new("libfoo")
+    ///     .cargo_metadata(false)
+    ///     .host_triple("x86_64-unknown-linux-gnu")
+    ///     .target_triple("armv7-linux-androideabi")
+    ///     .build();
+    /// ```
+    pub fn target_triple(&mut self, triple: &str) -> &mut Config {
+        self.target_triple = Some(triple.to_string());
+        self
+    }
+
+    /// Sets the Rust triple of the host, instead of `$HOST`.
+    pub fn host_triple(&mut self, triple: &str) -> &mut Config {
+        self.host = Some(triple.to_string());
+        self
+    }
+
+    /// Sets the xmake target for this compilation.
+    /// Note that is different from rust target (os and arch), an xmake target
+    /// can be binary or a library.
+    pub fn target(&mut self, target: &str) -> &mut Config {
+        self.targets([target])
+    }
+
+    /// Sets the xmake targets for this compilation, replacing the previous
+    /// ones.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let names = vec!["foo".to_string(), "bar".to_string()];
+    /// xmake::Config::new("libfoo").targets(&names).build();
+    /// ```
+    pub fn targets<I, S>(&mut self, targets: I) -> &mut Config
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.targets = targets
+            .into_iter()
+            .map(|t| t.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Builds every target of the project, including the ones that aren't
+    /// built by default, like plugins declared with `set_default(false)`.
+    ///
+    /// This has no effect when targets are selected with [`Config::targets`].
+    /// This option defaults to `false`.
+    pub fn all_targets(&mut self, value: bool) -> &mut Config {
+        self.all_targets = value;
+        self
+    }
+
+    /// Reports the packages resolved by xmake and their versions in a
+    /// `cargo:warning`, like `resolved zlib 1.3.1 (installed), fmt 10.2.1`,
+    /// where `installed` marks the packages installed by this build.
+    ///
+    /// The packages are always reported with [`Config::verbose`].
+    /// This option defaults to `false`.
+    pub fn report_packages(&mut self, value: bool) -> &mut Config {
+        self.report_packages = value;
+        self
+    }
+
+    /// Builds only the targets of the group `name`, declared with
+    /// `set_group`, passed as `--group=` to xmake.
+    ///
+    /// The name can be a pattern like `libs/*`. The group is ignored when
+    /// targets are selected with [`Config::targets`], which take precedence.
+    pub fn group(&mut self, name: &str) -> &mut Config {
+        self.group = Some(name.to_string());
+        self
+    }
+
+    /// Sets the runtimes passed to xmake with `--runtimes`, like `MT` or
+    /// `c++_shared`, instead of the ones inferred from the Rust target.
+    pub fn runtimes<I, S>(&mut self, runtimes: I) -> &mut Config
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.runtimes = runtimes
+            .into_iter()
+            .map(|r| r.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Sets verbose output.
+    ///
+    /// The output of xmake is always written to `xmake-build.log` in the
+    /// output directory, it is only printed when verbose or when the
+    /// `XMAKERS_VERBOSE` environment variable is set to `1` or `true`.
+    /// Cargo doesn't tell the build scripts that it runs with `-vv`, so set
+    /// the variable along with it to see the output of xmake.
+    ///
+    /// This is a shorthand for a [`Config::verbosity`] of `1` or `0`.
+    pub fn verbose(&mut self, value: bool) -> &mut Config {
+        self.verbosity = value as u8;
+        self
+    }
+
+    /// Sets the verbosity level of xmake, from `0` to `3`.
+    ///
+    /// * `0`: no extra output (default)
+    /// * `1`: verbose output (`-v`)
+    /// * `2` and `3`: verbose and diagnosis output (`-vD`)
+    ///
+    /// Levels above `3` are clamped.
+    pub fn verbosity(&mut self, level: u8) -> &mut Config {
+        self.verbosity = level.min(3);
+        self
+    }
+
+    /// Sets whether the libraries reported by xmake are automatically linked.
+    ///
+    /// This option defaults to `true`.
+    pub fn auto_link(&mut self, value: bool) -> &mut Config {
+        self.auto_link = value;
+        self
+    }
+
+    /// Sets the output directory for this compilation.
+    ///
+    /// This is automatically scraped from `$OUT_DIR` which is set for Cargo
+    /// build scripts so it's not necessary to call this from a build script.
+    ///
+    /// The libraries and headers are installed in this directory, which is
+    /// also where xmake builds the project unless [`Config::build_dir`] is set.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, out: P) -> &mut Config {
+        self.out_dir = Some(out.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the xmake mode for this compilation.
+    pub fn mode(&mut self, mode: &str) -> &mut Config {
+        self.mode = Some(mode.to_string());
+        self
+    }
+
+    /// Sets the xmake mode from a CMake build type, overriding the mode
+    /// inferred from the Cargo profile.
+    ///
+    /// | Build type       | xmake mode   |
+    /// |------------------|--------------|
+    /// | `Debug`          | `debug`      |
+    /// | `Release`        | `release`    |
+    /// | `RelWithDebInfo` | `releasedbg` |
+    /// | `MinSizeRel`     | `minsizerel` |
+    ///
+    /// The modes of xmake set the optimization and the debug symbols like
+    /// the build types of CMake. The names are case insensitive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't one of the build types above.
+    pub fn profile<S: AsRef<str>>(&mut self, name: S) -> &mut Config {
+        let name = name.as_ref();
+        let mode = [
+            ("Debug", "debug"),
+            ("Release", "release"),
+            ("RelWithDebInfo", "releasedbg"),
+            ("MinSizeRel", "minsizerel"),
+        ]
+        .into_iter()
+        .find(|(build_type, _)| build_type.eq_ignore_ascii_case(name));
+        match mode {
+            Some((_, mode)) => self.mode(mode),
+            None => panic!(
+                "unknown profile `{}`, expected Debug, Release, RelWithDebInfo or MinSizeRel",
+                name
+            ),
+        }
+    }
+
+    /// Builds with the xmake mode `mode`, like a custom mode added by the
+    /// project with `add_rules("mode.coverage")`, when the Cargo profile is
+    /// `profile`.
+    ///
+    /// The profile is the name of the profile directory in the target
+    /// directory, so custom profiles like `coverage` can be told apart from
+    /// the `release` profile they inherit from. The mappings are consulted
+    /// before the mode inferred from the profile, a mode set with
+    /// [`Config::mode`] takes precedence over them.
+    pub fn mode_for_profile(&mut self, profile: &str, mode: &str) -> &mut Config {
+        self.profile_modes.retain(|(p, _)| p != profile);
+        self.profile_modes
+            .push((profile.to_string(), mode.to_string()));
+        self
+    }
+
+    /// Uses the `check` mode of xmake instead of `debug` when the mode is
+    /// inferred from an unoptimized Rust profile.
+    ///
+    /// The project must add the `mode.check` rule, a warning is emitted
+    /// otherwise. Cargo doesn't tell the build scripts whether it runs
+    /// `cargo check` or `cargo build`, so enable this conditionally, for
+    /// example from a feature or an environment variable.
+    /// This option defaults to `false`.
+    pub fn prefer_check_mode(&mut self, value: bool) -> &mut Config {
+        self.prefer_check_mode = value;
+        self
+    }
+
+    /// Configure an option for the `xmake` processes spawned by
+    /// this crate in the `build` step.
+    ///
+    /// Booleans are converted to the `y`/`n` form expected by xmake, strings
+    /// and paths are passed unchanged, see [`OptionValue`]. Setting an option
+    /// again replaces its value, the options are passed in the order they
+    /// were first set.
+    pub fn option<K, V>(&mut self, key: K, value: V) -> &mut Config
+    where
+        K: AsRef<OsStr>,
+        V: Into<OptionValue>,
+    {
+        let key = key.as_ref();
+        let value = value.into().into_os_string();
+        match self.options.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.options.push((key.to_owned(), value)),
+        }
+        self
+    }
+
+    /// Removes the option `key` set with [`Config::option`].
+    pub fn remove_option<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Config {
+        self.options.retain(|(k, _)| k != key.as_ref());
+        self
+    }
+
+    /// Removes every option set with [`Config::option`].
+    pub fn clear_options(&mut self) -> &mut Config {
+        self.options.clear();
+        self
+    }
+
+    /// Fails the build when an option set with [`Config::option`] is unknown
+    /// to xmake and to the project, instead of emitting a warning.
+    ///
+    /// xmake ignores the unknown options, which hides typos in their names.
+    /// This option defaults to `false`.
+    pub fn strict_options(&mut self, value: bool) -> &mut Config {
+        self.strict_options = value;
+        self
+    }
+
+    /// Configure an option consumed by the target `target` only.
+    ///
+    /// xmake options are global to the project, so the option is only passed
+    /// when `target` is built, either selected with [`Config::target`] or
+    /// built by default. A warning is emitted if the project doesn't declare
+    /// `target`.
+    pub fn target_option<K, V>(&mut self, target: &str, key: K, value: V) -> &mut Config
+    where
+        K: AsRef<OsStr>,
+        V: Into<OptionValue>,
+    {
+        self.target_options.push((
+            target.to_string(),
+            key.as_ref().to_owned(),
+            value.into().into_os_string(),
+        ));
+        self
+    }
+
+    /// Configure an environment variable for the `xmake` processes spawned by
+    /// this crate in the `build` step.
+    pub fn env<K, V>(&mut self, key: K, value: V) -> &mut Config
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.env
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Configure several environment variables for the `xmake` processes
+    /// spawned by this crate, like [`Config::env`].
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Config
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (key, value) in vars {
+            self.env(key, value);
+        }
+        self
+    }
+
+    /// Removes an environment variable inherited by the `xmake` processes
+    /// spawned by this crate, like `CC` or `CFLAGS` that would confuse the
+    /// toolchain detection of xmake.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Config {
+        self.env_remove.push(key.as_ref().to_owned());
+        self
+    }
+
+    /// Configures runtime type (static or not)
+    ///
+    /// This option defaults to `false`.
+    pub fn static_crt(&mut self, static_crt: bool) -> &mut Config {
+        self.static_crt = Some(static_crt);
+        self
+    }
+
+    /// Set the standard library to link against when compiling with C++
+    /// support (only Android).
+    /// The given library name must not contain the `lib` prefix.
+    ///
+    ///
+    /// Common values:
+    /// - `c++_static`
+    /// - `c++_shared`
+    /// - `gnustl_static`
+    /// - `gnustl_shared`
+    /// - `stlport_shared`
+    /// - `stlport_static`
+    pub fn cpp_link_stdlib(&mut self, stblib: &str) -> &mut Config {
+        self.cpp_link_stdlib = Some(stblib.to_string());
+        self
+    }
+
+    /// Sets the default kind of the targets, passed as `--kind=` to xmake.
+    ///
+    /// This only affects targets declared with `set_kind("$(kind)")`, or the
+    /// ones without any kind, it doesn't change how packages are built.
+    ///
+    /// # Panics
+    ///
+    /// Only [`LinkKind::Static`] and [`LinkKind::Dynamic`] can be built, any
+    /// other kind will panic.
+    pub fn default_target_kind(&mut self, kind: LinkKind) -> &mut Config {
+        match kind {
+            LinkKind::Static | LinkKind::Dynamic => self.target_kind = Some(kind),
+            _ => panic!(
+                "{:?} is not a buildable kind, expected Static or Dynamic",
+                kind
+            ),
+        }
+        self
+    }
+
+    /// Sets whether the code is compiled as position independent, with
+    /// `-fPIC`.
+    ///
+    /// Shared libraries are always position independent, this matters for the
+    /// static libraries ([`LinkKind::Static`]) linked into a Rust `cdylib`,
+    /// which fails to link otherwise. It has no effect on Windows and wasm.
+    /// This option defaults to `false`, leaving it to the toolchain and the
+    /// project, as `-fPIC` isn't supported by every bare-metal target.
+    pub fn pic(&mut self, value: bool) -> &mut Config {
+        self.pic = value;
+        self
+    }
+
+    /// Forces the configuration step to run on every build.
+    ///
+    /// By default `xmake config` is skipped when the options, mode, platform,
+    /// environment, xmake executable and version are the same as the last
+    /// configuration.
+    /// This option defaults to `false`.
+    pub fn always_configure(&mut self, value: bool) -> &mut Config {
+        self.always_configure = value;
+        self
+    }
+
+    /// Allows xmake to run as root, by setting `XMAKE_ROOT=y` for the spawned
+    /// xmake processes.
+    ///
+    /// xmake refuses to run as root by default, which is common in minimal
+    /// containers. Note that the build scripts of the project and its packages
+    /// will then run with root privileges.
+    /// By default, this is enabled when the build script runs as root on Unix.
+    /// The `XMAKERS_ALLOW_ROOT` environment variable overrides the detection,
+    /// `0` or `false` disabling it and any other value enabling it.
+    pub fn allow_root(&mut self, value: bool) -> &mut Config {
+        self.allow_root = Some(value);
+        self
+    }
+
+    /// Resolves the packages from the system instead of building them, using
+    /// the `package.fetch_only` policy of xmake.
+    ///
+    /// This reduces the build time and respects the policies of the
+    /// distributions, but the build fails if a required package isn't
+    /// installed on the system.
+    /// This option defaults to `false`.
+    pub fn prefer_system_packages(&mut self, value: bool) -> &mut Config {
+        self.prefer_system_packages = value;
+        self
+    }
+
+    /// Builds the project with the sanitizer `kind`, using the
+    /// `build.sanitizer.*` policies of xmake, and links its runtime.
+    ///
+    /// This can be called multiple times to combine sanitizers, as far as the
+    /// toolchain allows it. The address, thread and memory sanitizers are
+    /// exclusive, combining them fails the build. On Windows, only
+    /// [`Sanitizer::Address`] is supported by MSVC, whose runtime is found by
+    /// the linker.
+    pub fn sanitizer(&mut self, kind: Sanitizer) -> &mut Config {
+        if !self.sanitizers.contains(&kind) {
+            self.sanitizers.push(kind);
+        }
+        self
+    }
+
+    /// Installs the packages required by the project with `xmake require`
+    /// once configured, instead of letting xmake download them on demand.
+    ///
+    /// This makes the builds from a fresh checkout deterministic, a missing
+    /// package makes the build fail before anything is compiled.
+    /// This option defaults to `false`.
+    pub fn install_packages(&mut self, value: bool) -> &mut Config {
+        self.install_packages = value;
+        self
+    }
+
+    /// Sets the minimum version of xmake required by the project.
+    ///
+    /// It can only raise the minimum version required by the crate itself.
+    pub fn minimum_version(&mut self, version: Version) -> &mut Config {
+        self.minimum_version = Some(version);
+        self
+    }
+
+    /// Returns the version of the installed xmake, or `None` if it couldn't
+    /// be run.
+    pub fn xmake_version(&mut self) -> Option<&Version> {
+        if self.cache.xmake_version.is_none() {
+            let (xmake, args) = self.xmake_executable();
+            self.cache.xmake_version = program_version(&xmake, &args);
+        }
+        self.cache.xmake_version.as_ref()
+    }
+
+    /// Disables any network access of xmake, for air-gapped machines.
+    ///
+    /// The `network.mode` policy is set to `private` during configure and
+    /// downloads are no longer accepted automatically, so a missing package
+    /// fails the build right away instead of waiting on a network timeout.
+    /// The packages must already be installed or available on the system.
+    /// The `network.mode` policy exists since xmake 2.8.5, the oldest version
+    /// supported by the crate.
+    /// This option defaults to `false`.
+    pub fn offline(&mut self, value: bool) -> &mut Config {
+        self.offline = value;
+        self
+    }
+
+    /// Sets the interval at which a `cargo:warning` reports that xmake is
+    /// still running, along with its progress if xmake printed one.
+    ///
+    /// A zero interval disables the report.
+    /// This option defaults to 60 seconds.
+    pub fn progress_interval(&mut self, interval: Duration) -> &mut Config {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Adds the package repository `name` located at `url`, a git url or a
+    /// local path, for example an internal mirror of xmake-repo, using the git
+    /// branch `branch` if any.
+    ///
+    /// This can be called several times. The repositories are added to the
+    /// project with `xmake repo --add` before the packages are resolved, so
+    /// the global configuration of xmake is left untouched.
+    pub fn repository(&mut self, name: &str, url: &str, branch: Option<&str>) -> &mut Config {
+        self.repos.push((
+            name.to_string(),
+            url.to_string(),
+            branch.map(str::to_string),
+        ));
+        self
+    }
+
+    /// Sets the path of the xmake executable, instead of the one found by
+    /// [`find_executable`] or given by the `XMAKE` environment variable.
+    pub fn xmake_path<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.xmake_path = Some(path.as_ref().to_path_buf());
+        // The version may have been resolved for another executable
+        self.cache.xmake_version = None;
+        self
+    }
+
+    /// Sets the global directory of xmake through `XMAKE_GLOBALDIR`, where
+    /// xmake looks for its repositories and installed packages.
+    ///
+    /// This is meant to point at a pre-populated directory, for networks where
+    /// the repositories can't be fetched.
+    pub fn global_repo_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.global_repo_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generates a `compile_commands.json` for the project once configured,
+    /// at `path` or in the output directory if `None`.
+    ///
+    /// Its location is then available from [`BuildInfo::compile_commands`],
+    /// even when the libraries are not automatically linked.
+    pub fn export_compile_commands(&mut self, path: Option<PathBuf>) -> &mut Config {
+        self.compile_commands = Some(path);
+        self
+    }
+
+    /// Sets whether xmake only rebuilds what changed since the last build.
+    ///
+    /// When disabled, the build step is run with `--rebuild` so every target
+    /// is rebuilt from scratch, which helps with suspected stale builds
+    /// without cleaning the build directory.
+    /// This option defaults to `true`.
+    pub fn incremental(&mut self, value: bool) -> &mut Config {
+        self.incremental = value;
+        self
+    }
+
+    /// Sets the name of the directory, relative to the install prefix, in
+    /// which the libraries are installed.
+    ///
+    /// This option defaults to `lib`.
+    pub fn install_libdir(&mut self, name: &str) -> &mut Config {
+        self.install_libdir = name.to_string();
+        self
+    }
+
+    /// Sets the name of the directory, relative to the install prefix, in
+    /// which the headers are installed.
+    ///
+    /// This option defaults to `include`.
+    pub fn install_includedir(&mut self, name: &str) -> &mut Config {
+        self.install_includedir = name.to_string();
+        self
+    }
+
+    /// Builds a universal macOS library for the architectures `archs`, like
+    /// `["x86_64", "arm64"]`.
+    ///
+    /// The project is built once per architecture, then the static and
+    /// dynamic libraries are merged with `lipo -create` into the output
+    /// directory, which is what gets linked. The packages used by the project
+    /// must themselves be universal. This is ignored on other platforms.
+    pub fn universal_macos(&mut self, archs: &[&str]) -> &mut Config {
+        self.universal_archs = archs.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// Enables the `package.requires_lock` policy, so the versions of the
+    /// packages are read from `xmake-requires.lock` in the project directory,
+    /// which is generated if missing.
+    ///
+    /// This option defaults to `false`.
+    pub fn lockfile(&mut self, value: bool) -> &mut Config {
+        self.lockfile = value;
+        self
+    }
+
+    /// Fails the build if `xmake-requires.lock` would be created or updated,
+    /// which implies [`Config::lockfile`].
+    ///
+    /// This is meant for CI, where the lock file must be up to date.
+    /// This option defaults to `false`.
+    pub fn frozen(&mut self, value: bool) -> &mut Config {
+        self.frozen = value;
+        self
+    }
+
+    /// Sets whether the system libraries needed by the C++ standard library
+    /// (`pthread`, `dl` and `m`) are linked on Linux when C++ is used.
+    ///
+    /// Disable it to manage these libraries manually.
+    /// This option defaults to `true`.
+    pub fn link_stl_deps(&mut self, value: bool) -> &mut Config {
+        self.link_stl_deps = value;
+        self
+    }
+
+    /// Sets whether the system libraries and frameworks are linked, like the
+    /// syslinks reported by xmake and the dependencies of the C++ standard
+    /// library.
+    ///
+    /// Disable it to manage the system libraries manually, when they are
+    /// already linked by the Rust standard library or conflict with it.
+    /// This option defaults to `true`.
+    pub fn emit_system_libs(&mut self, value: bool) -> &mut Config {
+        self.emit_system_libs = value;
+        self
+    }
+
+    /// Sets whether the shared libraries of the install directory are linked
+    /// on Linux, as xmake doesn't report the ones installed by the packages.
+    ///
+    /// They are linked in alphabetical order after the libraries reported by
+    /// xmake, which may not be the order they depend on each other. Disable
+    /// it if the link fails because of the order, and link the libraries
+    /// with [`Config::rustc_link_arg`] or the syslinks of the project instead.
+    /// This option defaults to `true`.
+    pub fn scan_shared_libs(&mut self, value: bool) -> &mut Config {
+        self.scan_shared_libs = value;
+        self
+    }
+
+    /// Sets the number of parallel jobs used to build the project.
+    ///
+    /// By default, the `NUM_JOBS` environment variable set by Cargo is used.
+    /// If it is missing, the number of jobs is derived from the CPU count and
+    /// the available memory of the host, to avoid running out of memory on
+    /// large C++ builds. On macOS, the total memory of the host is used
+    /// instead, as the available one isn't exposed by `sysctl`.
+    pub fn jobs(&mut self, jobs: usize) -> &mut Config {
+        self.jobs = Some(jobs.max(1));
+        self
+    }
+
+    /// Sets the number of parallel link jobs, passed as `--linkjobs=` to
+    /// `xmake build`, to limit the memory used when linking large libraries.
+    ///
+    /// The link jobs are part of the jobs set by [`Config::jobs`], so only a
+    /// value lower than them has an effect. This requires xmake 2.9.1, it is
+    /// ignored with a warning on older versions.
+    pub fn link_jobs(&mut self, jobs: usize) -> &mut Config {
+        self.link_jobs = Some(jobs.max(1));
+        self
+    }
+
+    /// Sets the xmake toolchain used to build the project, like `clang`, `gcc`
+    /// or a toolchain registered by the project.
+    ///
+    /// This takes precedence over the toolchain selected by the crate when
+    /// cross compiling.
+    pub fn toolchain(&mut self, name: &str) -> &mut Config {
+        self.toolchain = Some(name.to_string());
+        self
+    }
+
+    /// Sets the directory of the SDK of the toolchain, passed as `--sdk`.
+    ///
+    /// When cross compiling, the SDK is guessed by default from the compiler
+    /// found by the `cc` crate, which can be wrong for unusual toolchain
+    /// layouts.
+    pub fn sdk<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.sdk = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the prefix of the cross compilation tools, like `arm-none-eabi`,
+    /// passed as `--cross`.
+    ///
+    /// By default, the prefix is made from the architecture and the OS of the
+    /// Rust target.
+    pub fn cross(&mut self, triple: &str) -> &mut Config {
+        self.cross = Some(triple.to_string());
+        self
+    }
+
+    /// Links the whole static library `name`, instead of only the objects
+    /// used by the crate, for libraries registering themselves through static
+    /// initializers.
+    ///
+    /// This uses the `+whole-archive` linking modifier, which requires Rust
+    /// 1.61 or later.
+    pub fn whole_archive(&mut self, name: &str) -> &mut Config {
+        self.whole_archives.push(name.to_string());
+        self
+    }
+
+    /// Links every static library as a whole, see [`Config::whole_archive`].
+    ///
+    /// This option defaults to `false`.
+    pub fn whole_archive_all(&mut self, value: bool) -> &mut Config {
+        self.whole_archive_all = value;
+        self
+    }
+
+    /// Merges the installed static libraries into a single archive `name`,
+    /// linked in place of them, to avoid depending on their link order.
+    ///
+    /// The archive is created in the library directory by `lib.exe` on
+    /// Windows, `libtool` on Apple platforms and `ar` elsewhere. The shared
+    /// and system libraries are still linked separately. Outside of Windows,
+    /// the build fails if a symbol is defined by several of the libraries.
+    pub fn bundle_static(&mut self, name: &str) -> &mut Config {
+        self.bundle = Some(name.to_string());
+        self
+    }
+
+    /// Adds an argument passed to the linker as is, like `-Wl,-rpath,$ORIGIN`,
+    /// emitted as `cargo:rustc-link-arg` when the libraries are linked.
+    ///
+    /// This can be called multiple times to add several arguments.
+    pub fn rustc_link_arg<S: AsRef<str>>(&mut self, arg: S) -> &mut Config {
+        self.link_args.push(arg.as_ref().to_string());
+        self
+    }
+
+    /// Sets the rpath added to the Rust binaries, so they find the shared
+    /// libraries at runtime without setting `LD_LIBRARY_PATH`.
+    ///
+    /// [`RpathMode::Origin`] is meant to be combined with
+    /// [`Config::copy_dylibs_to_target`]. This has no effect on Windows, where
+    /// the DLLs must be next to the binary or in `PATH`.
+    /// This option defaults to [`RpathMode::None`].
+    pub fn rpath(&mut self, mode: RpathMode) -> &mut Config {
+        self.rpath = mode;
+        self
+    }
+
+    /// Removes the output directory when the configuration is dropped, for
+    /// builds in temporary directories like in test harnesses.
+    ///
+    /// Only an output directory set with [`Config::out_dir`] inside the
+    /// temporary directory of the system is removed, never the `OUT_DIR` of
+    /// Cargo.
+    /// A clone of the configuration doesn't remove the directory, unless this
+    /// option is set again on the clone.
+    /// This option defaults to `false`.
+    pub fn clean_on_drop(&mut self, value: bool) -> &mut Config {
+        self.clean_on_drop = OwnedFlag(value);
+        self
+    }
+
+    /// Prints the xmake commands run and the environment variables read to
+    /// stderr, to debug the build script.
+    ///
+    /// The commands are always written to the build log. With the `log`
+    /// feature, these lines are also emitted as `debug!` and `trace!` records,
+    /// whether or not this option is set.
+    /// This option defaults to `false`.
+    pub fn verbose_commands(&mut self, value: bool) -> &mut Config {
+        self.verbose_commands = value;
+        self
+    }
+
+    /// Silences the output of the crate, except for the `cargo:` directives.
+    ///
+    /// The output of xmake is neither forwarded when cargo is verbose nor
+    /// reported by the progress warnings, it is buffered and only printed when
+    /// a command fails. [`Config::verbose`] still forwards the output.
+    /// This option defaults to `false`.
+    pub fn quiet(&mut self, value: bool) -> &mut Config {
+        self.quiet = value;
+        self
+    }
+
+    /// Sets whether the `cargo:` directives are printed, to use the crate
+    /// outside of a build script.
+    ///
+    /// When disabled, nothing is linked, the warnings are printed to the
+    /// standard error and the variables set by cargo fall back to defaults:
+    /// the host of `rustc` for the host and target, a release build and an
+    /// output directory in the temporary directory, unless set with
+    /// [`Config::out_dir`].
+    /// This option defaults to `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let dst = Config::new("libfoo").cargo_metadata(false).out_dir("build").build();
+    /// println!("installed in {}", dst.display());
+    /// ```
+    pub fn cargo_metadata(&mut self, value: bool) -> &mut Config {
+        self.cargo_metadata = value;
+        self
+    }
+
+    /// Restricts the include directories of the packages kept in
+    /// [`BuildInfo`] to the packages `names`, to leave out the transitive
+    /// dependencies when generating bindings.
+    ///
+    /// By default, the include directories of every package are kept.
+    pub fn export_package_includes(&mut self, names: &[&str]) -> &mut Config {
+        self.exported_packages = Some(names.iter().map(|n| n.to_string()).collect());
+        self
+    }
+
+    /// Generates the header `out` including every header installed in the
+    /// output directory once built, to give a single header to bindgen.
+    ///
+    /// The headers are included relatively to the include directory, which
+    /// must be in the include paths, in a stable order.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::PathBuf;
+    ///
+    /// let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("wrapper.h");
+    /// xmake::Config::new("libfoo")
+    ///     .generate_wrapper_header(&out)
+    ///     .build();
+    /// ```
+    pub fn generate_wrapper_header<P: AsRef<Path>>(&mut self, out: P) -> &mut Config {
+        self.wrapper_header = Some(out.as_ref().to_path_buf());
+        self
+    }
+
+    /// Copies the built shared libraries to the profile directory of the
+    /// target directory, like `target/debug`, so that the binaries find them
+    /// when run outside of cargo.
+    ///
+    /// This has no effect when only static libraries are built.
+    /// This option defaults to `false`.
+    pub fn copy_dylibs_to_target(&mut self, value: bool) -> &mut Config {
+        self.copy_dylibs = value;
+        self
+    }
+
+    /// Sets whether the project directory is locked during the build, so that
+    /// the build scripts of several crates building the same project wait for
+    /// each other instead of corrupting the configuration of xmake.
+    ///
+    /// The lock is a file of the temporary directory of the system.
+    /// This option defaults to `true`.
+    pub fn locking(&mut self, value: bool) -> &mut Config {
+        self.locking = value;
+        self
+    }
+
+    /// Sets the directory in which xmake builds the project, the output
+    /// directory by default.
+    ///
+    /// The libraries and headers are still installed in the output directory.
+    /// The build directory is locked during the build, so it can be shared by
+    /// several cargo processes. It also holds the `.xmake` configuration of
+    /// the project, so it must be outside the project directory, which is
+    /// watched by cargo, or the build script is rerun on every build.
+    pub fn build_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Config {
+        self.build_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Builds the project in a directory of the target directory that is
+    /// kept when the output directory is cleaned, so the objects are reused
+    /// when the crate is rebuilt from scratch.
+    ///
+    /// The directory depends on the project, the Rust target, and everything
+    /// passed to xmake to configure it, like the mode, the options, the
+    /// toolchain or the environment. It is ignored if [`Config::build_dir`] is
+    /// set.
+    /// This option defaults to `false`.
+    pub fn shared_build_cache(&mut self, value: bool) -> &mut Config {
+        self.shared_build_cache = value;
+        self
+    }
+
+    /// Clears the information computed by the last build, like the one
+    /// returned by [`Config::build_info`].
+    ///
+    /// This is done at the start of every [`Config::build`], so the options
+    /// can be changed between two builds.
+    pub fn reset_cache(&mut self) -> &mut Config {
+        self.cache.reset();
+        self
+    }
+
+    /// Runs [`Config::build`] in a background thread, returning a handle to
+    /// wait for the build or cancel it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let handle = Config::new("libfoo").spawn();
+    /// handle.cancel();
+    /// assert!(handle.join().is_err());
+    /// ```
+    pub fn spawn(mut self) -> BuildHandle {
+        let cancel = CancelToken::default();
+        self.cancel = Some(cancel.clone());
+        BuildHandle {
+            thread: thread::spawn(move || self.build()),
+            cancel,
+        }
+    }
+
+    /// Run this configuration, compiling the library with all the configured
+    /// options.
+    ///
+    /// This will run both the configuration command as well as the
+    /// command to build the library.
+    pub fn build(&mut self) -> PathBuf {
+        self.reset_cache();
+
+        // Start from a fresh log, it is appended by each xmake command
+        let _ = fs::remove_file(self.get_out_dir().join("xmake-build.log"));
+
+        if self.package.is_some() {
+            self.generate_package_project();
+        }
+
+        let lockfile = self.get_project_dir().join("xmake-requires.lock");
+        let lock = fs::read(&lockfile).ok();
+
+        let _project_lock = self.lock_project();
+        let _lock = self.lock_build_dir();
+
+        self.check_version();
+        self.add_repos();
+        self.config();
+        self.check_mode_rule();
+        self.check_options();
+        self.check_target_options();
+        if self.install_packages {
+            self.require();
+        }
+        for cmd in self.requires_commands() {
+            self.run_require(cmd);
+        }
+
+        if self.frozen && fs::read(&lockfile).ok() != lock {
+            // Restore the lock file so the failure can be reproduced
+            let _ = match lock.as_ref() {
+                Some(lock) => fs::write(&lockfile, lock),
+                None => fs::remove_file(&lockfile),
+            };
+            fail(&format!(
+                "{} is out of date, update it without Config::frozen",
+                lockfile.display()
+            ));
+        }
+        if lockfile.exists() {
+            self.cargo_directive(&format!("rerun-if-changed={}", lockfile.display()));
+        }
+        let compile_commands = self.compile_commands.clone().map(|path| {
+            let path = match path {
+                Some(path) => env::current_dir().unwrap().join(path),
+                None => self.get_out_dir().join("compile_commands.json"),
+            };
+            self.generate_compile_commands(&path);
+            path
+        });
+
+        let universal = !self.universal_archs.is_empty();
+        if universal && self.get_triple().os == "macos" {
+            self.build_universal();
+        } else {
+            if universal {
+                self.warn("universal binaries are only supported on macOS, ignoring");
+            }
+            self.run_build();
+            self.install();
+        }
+
+        // XMake put libary in the lib folder
+        let dst = self.get_out_dir().join(&self.install_libdir);
+        self.cargo_directive(&format!("root={}", dst.display()));
+
+        // xmake leaves the debug symbols in the build directory
+        let mut debug_symbols = Vec::new();
+        find_debug_symbols(&self.get_out_dir(), &mut debug_symbols);
+        if self.get_build_dir() != self.get_out_dir() {
+            find_debug_symbols(&self.get_build_dir(), &mut debug_symbols);
+        }
+        debug_symbols.sort();
+        let mut installed = match self.get_mode() {
+            "debug" | "releasedbg" => self.install_debug_files(&debug_symbols, &dst),
+            _ => Vec::new(),
+        };
+        let installed_debug_symbols = installed.len();
+        debug_symbols.retain(|path| !installed.contains(path));
+        installed.append(&mut debug_symbols);
+
+        if self.copy_dylibs {
+            self.copy_dylibs_to_profile_dir();
+        }
+        if let Some(path) = self.wrapper_header.clone() {
+            self.write_wrapper_header(&path);
+        }
+
+        self.cache.build_info = self.get_build_info();
+        let packages = self.cache.build_info.packages();
+        if (self.report_packages || self.verbosity > 0) && !packages.is_empty() {
+            self.warn(&format!(
+                "xmake: {}",
+                package_summary(packages, &self.cache.installed_packages)
+            ));
+        }
+        if let Some(names) = self.exported_packages.as_ref() {
+            self.cache
+                .build_info
+                .includedirs_package
+                .retain(|name, _| names.contains(name));
+        }
+        self.cache.build_info.compile_commands = compile_commands;
+        self.cache.build_info.libdir = Some(dst.clone());
+        self.cache.build_info.debug_symbols = installed;
+        self.cache.build_info.installed_debug_symbols = installed_debug_symbols;
+        if self.auto_link && self.cargo_metadata {
+            self.link();
+        }
+
+        dst
+    }
+
+    /// Runs the build step.
+    fn run_build(&mut self) {
+        let mut cmd = self.xmake_command();
+        cmd.task("build");
+
+        if !self.incremental {
+            cmd.arg("--rebuild");
+        }
+
+        // In case of xmake is waiting to download something
+        if !self.offline {
+            cmd.arg("--yes");
+        }
+
+        let jobs = match self.jobs {
+            Some(jobs) => jobs,
+            None => self
+                .getenv_os("NUM_JOBS")
+                .and_then(|s| s.to_str()?.parse().ok())
+                .unwrap_or_else(auto_jobs),
+        };
+        cmd.arg(format!("--jobs={}", jobs));
+
+        if let Some(link_jobs) = self.link_jobs {
+            if self.cache.xmake_version >= Some(Version::new(2, 9, 1)) {
+                cmd.arg(format!("--linkjobs={}", link_jobs));
+            } else {
+                self.warn("link jobs require xmake 2.9.1 or newer, ignoring");
+            }
+        }
+
+        // xmake builds a single target per invocation
+        let mut cmds = Vec::new();
+        if self.targets.is_empty() {
+            if let Some(group) = self.group.as_ref() {
+                cmd.arg(format!("--group={}", group));
+            } else if self.all_targets {
+                cmd.arg("--all");
+            }
+            cmds.push(cmd.clone());
+        }
+        for target in self.targets.iter() {
+            let mut cmd = cmd.clone();
+            cmd.arg(target_name(target));
+            cmds.push(cmd);
+        }
+        for mut cmd in cmds {
+            match cmd.run() {
+                Ok(stdout) => self.cache.rebuilt |= !self.incremental || did_build(&stdout),
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+    }
+
+    /// Builds the project once per architecture of [`Config::universal_macos`]
+    /// and merges the produced libraries with `lipo` into the output directory.
+    fn build_universal(&mut self) {
+        let out_dir = self.get_out_dir();
+        let user_out_dir = self.out_dir.take();
+
+        let mut installs = Vec::new();
+        for arch in self.universal_archs.clone() {
+            let arch_dir = out_dir.join("xmakers").join("universal").join(&arch);
+            self.out_dir = Some(arch_dir.clone());
+            self.build_arch = Some(arch);
+            // The configuration of the previous architecture is overwritten
+            self.config();
+            self.run_build();
+            self.install();
+            installs.push(arch_dir);
+        }
+        self.out_dir = user_out_dir;
+        self.build_arch = None;
+
+        let libdir = out_dir.join(&self.install_libdir);
+        if let Err(e) = fs::create_dir_all(&libdir) {
+            fail(&format!("failed to create {}: {}", libdir.display(), e));
+        }
+        let entries = fs::read_dir(installs[0].join(&self.install_libdir))
+            .map(|entries| entries.flatten().collect::<Vec<_>>())
+            .unwrap_or_default();
+        for entry in entries {
+            let name = entry.file_name();
+            let path = Path::new(&name);
+            let output = libdir.join(&name);
+            match path.extension().and_then(OsStr::to_str) {
+                Some("a") | Some("dylib") => {
+                    let mut cmd = Command::new("lipo");
+                    cmd.arg("-create").arg("-output").arg(&output);
+                    for install in installs.iter() {
+                        cmd.arg(install.join(&self.install_libdir).join(&name));
+                    }
+                    match cmd.status() {
+                        Ok(status) if status.success() => {}
+                        Ok(status) => fail(&format!("lipo failed for {:?}: {}", name, status)),
+                        Err(e) => fail(&format!("failed to run lipo: {}", e)),
+                    }
+                }
+                _ => {
+                    if let Err(e) = copy_dir_all(&entry.path(), &output) {
+                        fail(&format!("failed to copy {}: {}", entry.path().display(), e));
+                    }
+                }
+            }
+        }
+
+        // The headers are the same for every architecture
+        let includedir = installs[0].join(&self.install_includedir);
+        if includedir.exists() {
+            if let Err(e) = copy_dir_all(&includedir, &out_dir.join(&self.install_includedir)) {
+                fail(&format!("failed to copy {}: {}", includedir.display(), e));
+            }
+        }
+    }
+
+    /// Evaluates the lua expression `expr` in the context of the project and
+    /// returns its value, or `None` if it evaluates to `nil`.
+    ///
+    /// The configuration of the project is loaded, so `config.get("plat")`
+    /// can be queried once the project is configured. This is an escape
+    /// hatch for the information not covered by [`BuildInfo`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let host = Config::new("libfoo").query_variable("os.host()");
+    /// ```
+    pub fn query_variable(&mut self, expr: &str) -> Option<String> {
+        let script = format!(
+            r#"import("core.project.config")
+
+function main()
+    os.cd(os.projectdir())
+    config.load()
+    local value = ({})
+    print("__xmakers_start__")
+    if value ~= nil then
+        print(tostring(value))
+    end
+    print("__xmakers_end__")
+end
+"#,
+            expr
+        );
+        let output = self.run_script("query_variable.lua", &script);
+        let value = output.strip_suffix('\n').unwrap_or(&output);
+        (!output.is_empty()).then(|| value.to_string())
+    }
+
+    /// Returns the names of the targets declared by the project.
+    ///
+    /// The project is configured first if needed, so that the targets are
+    /// resolved with the same options as the build.
+    pub fn list_targets(&mut self) -> Vec<String> {
+        self.check_version();
+        self.config();
+
+        self.get_targets()
+    }
+
+    /// Runs `targets.lua` to get the targets of the configured project.
+    fn get_targets(&mut self) -> Vec<String> {
+        let output = self.run_script("targets.lua", include_str!("targets.lua"));
+        parse_info_pairs(output)
+            .remove("targets")
+            .unwrap_or_default()
+    }
+
+    /// Warns about the options of [`Config::target_option`] given for targets
+    /// that the project doesn't declare.
+    fn check_target_options(&mut self) {
+        if self.target_options.is_empty() {
+            return;
+        }
+        let targets = self.get_targets();
+        for (target, key, _) in self.target_options.iter() {
+            if !targets.contains(target) {
+                self.warn(&format!(
+                    "option `{}` is set for the unknown target `{}`, the project declares: {}",
+                    key.to_string_lossy(),
+                    target,
+                    targets.join(", ")
+                ));
+            }
+        }
+    }
+
+    /// Warns if the `check` mode is used but no target of the project adds the
+    /// `mode.check` rule, in which case xmake builds without any flags.
+    fn check_mode_rule(&mut self) {
+        if self.get_mode() != "check" {
+            return;
+        }
+        let has_rule = self.query_variable(
+            r#"(function()
+        import("core.project.project")
+        for _, target in pairs(project.targets()) do
+            if target:rule("mode.check") then
+                return true
+            end
+        end
+        return false
+    end)()"#,
+        );
+        if has_rule.as_deref() == Some("false") {
+            self.warn("the check mode is used but the project doesn't add the `mode.check` rule");
+        }
+    }
+
+    /// Warns about the options that neither xmake nor the project declare,
+    /// suggesting the closest known option.
+    fn check_options(&mut self) {
+        let keys: Vec<String> = self
+            .options
+            .iter()
+            .map(|(key, _)| key)
+            .chain(self.target_options.iter().map(|(_, key, _)| key))
+            .map(|key| key.to_string_lossy().into_owned())
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        let known = self.known_options();
+        if known.is_empty() {
+            return;
+        }
+        for key in keys.iter().filter(|key| !known.contains(*key)) {
+            let message = unknown_option_message(key, &known);
+            if self.strict_options {
+                fail(&message);
+            }
+            self.warn(&message);
+        }
+    }
+
+    /// Returns the options accepted by `xmake config`, including the ones of
+    /// the project, parsed from `xmake config --help`.
+    ///
+    /// The list is cached in the build directory as long as the configuration
+    /// doesn't change.
+    fn known_options(&mut self) -> Vec<String> {
+        let dst = self.get_out_dir().join("xmakers");
+        let fingerprint = fs::read_to_string(dst.join("config.fingerprint")).unwrap_or_default();
+        let cache_file = dst.join("options.cache");
+        if let Ok(cache) = fs::read_to_string(&cache_file) {
+            if let Some((hash, options)) = cache.split_once('\n') {
+                if !fingerprint.is_empty() && hash == fingerprint {
+                    return options.split('|').map(String::from).collect();
+                }
+            }
+        }
+
+        let mut cmd = self.xmake_command();
+        cmd.echo = false;
+        let Ok(help) = cmd.task("config").arg("--help").run() else {
+            return Vec::new();
+        };
+        let mut options = Vec::new();
+        for word in help.split(|c: char| c.is_whitespace() || c == ',') {
+            if let Some(option) = word.strip_prefix("--") {
+                let name = option.split('=').next().unwrap_or_default();
+                if !name.is_empty() && !options.iter().any(|o| o == name) {
+                    options.push(name.to_string());
+                }
+            }
+        }
+        let _ = fs::write(
+            &cache_file,
+            format!("{}\n{}", fingerprint, options.join("|")),
+        );
+        options
+    }
+
+    /// Returns the files that [`Config::build`] will install in the output
+    /// directory for the selected targets, without building them.
+    ///
+    /// The project is configured first if needed. Only the files produced by
+    /// the targets themselves are listed, not the headers nor the files of
+    /// the packages.
+    pub fn planned_artifacts(&mut self) -> Vec<PathBuf> {
+        self.check_version();
+        self.config();
+
+        let dst = self.get_out_dir();
+        let output = self.run_script(
+            "artifacts.lua",
+            concat!(
+                include_str!("select_targets.lua"),
+                include_str!("artifacts.lua")
+            ),
+        );
+        artifact_paths(&output, &dst, &self.install_libdir)
+    }
+
+    /// Returns the flags xmake passes to the compilers and the linker for the
+    /// selected targets, including the ones set by the crate and the options.
+    ///
+    /// The project is configured first if needed, but not built. The flags of
+    /// the targets are merged, without duplicates.
+    pub fn resolved_flags(&mut self) -> ResolvedFlags {
+        self.check_version();
+        self.config();
+
+        let output = self.run_script(
+            "flags.lua",
+            concat!(
+                include_str!("select_targets.lua"),
+                include_str!("flags.lua")
+            ),
+        );
+        let mut map = parse_info_pairs(output);
+        let mut take = |key: &str| map.remove(key).unwrap_or_default();
+        ResolvedFlags {
+            cflags: take("cflags"),
+            cxxflags: take("cxxflags"),
+            ldflags: take("ldflags"),
+        }
+    }
+
+    /// Returns whether the last [`Config::build`] compiled, linked or archived
+    /// anything, to skip the work depending on the native libraries when they
+    /// are up to date.
+    ///
+    /// This is a heuristic based on the progress lines printed by xmake, like
+    /// `[ 50%]: compiling.release src/foo.c`. A build doing work without
+    /// printing such a line, like a custom rule, isn't detected, and a
+    /// rebuild of an object with the same content is still reported.
+    /// A build which isn't incremental always counts as a rebuild.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let mut config = Config::new("libfoo");
+    /// config.build();
+    /// if config.was_rebuilt() {
+    ///     // Regenerate the bindings only when the library changed
+    /// }
+    /// ```
+    pub fn was_rebuilt(&self) -> bool {
+        self.cache.rebuilt
+    }
+
+    /// Returns the information reported by xmake about the last build.
+    ///
+    /// This is empty until [`Config::build`] has completed.
+    pub fn build_info(&self) -> &BuildInfo {
+        &self.cache.build_info
+    }
+
+    /// Emits the cargo directives to link the libraries reported by xmake.
+    fn link(&mut self) {
+        let options = self.link_options();
+        let info = &self.cache.build_info;
+        let (mut directives, mut links) = info.collect_links(&options);
+        if let Some(name) = self.bundle.as_ref() {
+            let mut dirs = vec![options.libdir(info)];
+            dirs.extend_from_slice(info.linkdirs());
+            links = self.bundle_static_libs(name, links, &dirs, &options.plat);
+        }
+        directives.extend(info.lib_directives(&links, &options));
+        for directive in directives {
+            println!("cargo:{}", directive);
+        }
+    }
+
+    /// Returns the options of [`BuildInfo::emit_link_directives`] matching
+    /// the configuration.
+    fn link_options(&self) -> LinkOptions {
+        let mut options = LinkOptions::new(self.cache.plat.as_deref().unwrap_or_default());
+        options.libdir = Some(self.get_out_dir().join(&self.install_libdir));
+        options.cpp_link_stdlib = self.cpp_link_stdlib.clone();
+        options.link_stl_deps = self.link_stl_deps;
+        options.emit_system_libs = self.emit_system_libs;
+        options.scan_shared_libs = self.scan_shared_libs;
+        options.whole_archives = self.whole_archives.clone();
+        options.whole_archive_all = self.whole_archive_all;
+        options.rpath = self.rpath;
+        options.sanitizers = self.sanitizers.clone();
+        options.link_args = self.link_args.clone();
+
+        // The shared C++ runtime of the NDK is not in the default search paths
+        // of the linker.
+        if options.plat == "android" {
+            let ndk = self
+                .getenv("ANDROID_NDK_HOME")
+                .or_else(|| self.getenv("ANDROID_NDK_ROOT"));
+            if let Some(ndk) = ndk {
+                let target = self.get_target();
+                let api = self.getenv("ANDROID_PLATFORM");
+                for dir in get_ndk_libdirs(Path::new(&ndk), &target, api.as_deref()) {
+                    options.search_path(dir);
+                }
+            }
+        }
+        options
+    }
+
+    /// Merges the static libraries of `links` found in `dirs` into the archive
+    /// `name`, returning the links with the archive in place of the first of
+    /// them.
+    fn bundle_static_libs(
+        &self,
+        name: &str,
+        links: OrderedSet<Link>,
+        dirs: &[PathBuf],
+        plat: &str,
+    ) -> OrderedSet<Link> {
+        let file_name = |name: &str| match plat {
+            "windows" => format!("{}.lib", name),
+            _ => format!("lib{}.a", name),
+        };
+        let archives: Vec<PathBuf> = links
+            .items
+            .iter()
+            .filter(|link| link.kind() == LinkKind::Static)
+            .filter_map(|link| {
+                let file = file_name(link.name());
+                dirs.iter().map(|dir| dir.join(&file)).find(|p| p.is_file())
+            })
+            .collect();
+        if archives.is_empty() {
+            return links;
+        }
+        if plat != "windows" {
+            check_duplicate_symbols(&archives);
+        }
+
+        let output = dirs[0].join(file_name(name));
+        let _ = fs::remove_file(&output);
+        let status = match plat {
+            "windows" => {
+                let target = self.get_target();
+                let mut cmd = cc::windows_registry::find(&target, "lib.exe")
+                    .unwrap_or_else(|| Command::new("lib.exe"));
+                cmd.arg("/NOLOGO").arg(format!("/OUT:{}", output.display()));
+                cmd.args(&archives).status()
+            }
+            "macosx" | "iphoneos" | "appletvos" | "watchos" => Command::new("libtool")
+                .arg("-static")
+                .arg("-o")
+                .arg(&output)
+                .args(&archives)
+                .status(),
+            _ => {
+                // The MRI script of ar adds the members of the archives, not
+                // the archives themselves
+                let mut script = format!("CREATE {}\n", output.display());
+                for archive in archives.iter() {
+                    script.push_str(&format!("ADDLIB {}\n", archive.display()));
+                }
+                script.push_str("SAVE\nEND\n");
+
+                let ar = self.getenv("AR").unwrap_or_else(|| "ar".to_string());
+                Command::new(ar)
+                    .arg("-M")
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .and_then(|mut child| {
+                        child.stdin.take().unwrap().write_all(script.as_bytes())?;
+                        child.wait()
+                    })
+            }
+        };
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => fail(&format!(
+                "failed to create {}: the archiver exited with {}",
+                output.display(),
+                status
+            )),
+            Err(e) => fail(&format!("failed to create {}: {}", output.display(), e)),
+        }
+
+        let mut bundled = OrderedSet::new();
+        for link in links.items {
+            let file = file_name(link.name());
+            let is_bundled = link.kind() == LinkKind::Static
+                && archives
+                    .iter()
+                    .any(|a| a.file_name() == Some(OsStr::new(&file)));
+            match is_bundled {
+                true => bundled.insert(Link::new(name, LinkKind::Static)),
+                false => bundled.insert(link),
+            };
+        }
+        bundled
+    }
+
+    /// Runs `build_info.lua` to gather the information about the build.
+    fn get_build_info(&mut self) -> BuildInfo {
+        let output = self.run_script(
+            "build_info.lua",
+            concat!(
+                include_str!("select_targets.lua"),
+                include_str!("build_info.lua")
+            ),
+        );
+        match output.parse() {
+            Ok(info) => info,
+            Err(e) => fail(&format!("failed to parse the build information: {}", e)),
+        }
+    }
+
+    /// Writes the lua script `content` in the build directory and runs it with
+    /// `xmake lua`, returning the output printed between the markers.
+    fn run_script(&mut self, name: &str, content: &str) -> String {
+        let dst = self.get_out_dir().join("xmakers");
+        let script = dst.join(name);
+        if let Err(e) = fs::create_dir_all(&dst).and_then(|_| fs::write(&script, content)) {
+            fail(&format!("failed to write {}: {}", script.display(), e));
+        }
+
+        let mut cmd = self.xmake_command();
+        cmd.task("lua").arg(script);
+        if !self.targets.is_empty() {
+            cmd.env("XMAKERS_TARGETS", join_escaped(&self.targets));
+        } else if let Some(group) = self.group.as_ref() {
+            cmd.env("XMAKERS_GROUP", group);
+        } else if self.all_targets {
+            cmd.env("XMAKERS_ALL_TARGETS", "y");
+        }
+
+        match cmd.execute() {
+            Some(output) => output,
+            None => fail(&format!("{} didn't print any information", name)),
+        }
+    }
+
+    // Run the configuration with all the configured
+    /// options.
+    fn config(&mut self) {
+        let mut cmd = self.xmake_command();
+        cmd.task("config");
+
+        // In case of xmake is waiting to download something
+        if !self.offline {
+            cmd.arg("--yes");
+        }
+
+        let dst = self.get_out_dir();
+        let build_dir = self.get_build_dir();
+
+        cmd.arg(format!("--buildir={}", build_dir.display()));
+
+        // Cross compilation
+        let host = self.get_host();
+        let target = self.get_target();
+
+        // List of xmake platform https://github.com/xmake-io/xmake/tree/master/xmake/platforms
+        let triple = self.get_triple();
+        let plat = self.cache.plat.clone().or_else(|| get_xmake_plat(&triple));
+        let plat = match plat {
+            Some(p) => p,
+            None => panic!("unsupported rust target: {}", triple.os),
+        };
+
+        let build_arch = self.build_arch.as_deref();
+        cmd.args(plat_args(&plat, &triple, host != target, build_arch));
+        if host != target {
+            if plat == "android" {
+                if let Some(ndk) = self.getenv("ANDROID_NDK_HOME") {
+                    cmd.arg(format!("--ndk={}", ndk));
+                }
+                if self.cpp_link_stdlib.is_some() {
+                    cmd.arg(format!(
+                        "--ndk_cxxstl={}",
+                        self.cpp_link_stdlib.clone().unwrap()
+                    ));
+                }
+                if self.toolchain.is_none() {
+                    cmd.arg(format!("--toolchain={}", "ndk"));
+                }
+            }
+
+            if plat == "wasm" {
+                if let Some(emscripten) = self.getenv("EMSCRIPTEN_HOME") {
+                    cmd.arg(format!("--emsdk={}", emscripten));
+                }
+                if self.toolchain.is_none() {
+                    cmd.arg(format!("--toolchain={}", "emcc"));
+                }
+            }
+
+            if plat == "cross" && self.toolchain.is_none() {
+                if self.sdk.is_none() {
+                    let mut c_cfg = cc::Build::new();
+                    c_cfg
+                        .cargo_metadata(false)
+                        .opt_level(0)
+                        .debug(false)
+                        .warnings(false)
+                        .host(&host)
+                        .target(&target);
+
+                    // Attempt to find the cross compilation sdk
+                    // Let cc find it for us
+                    // Usually a compiler is inside bin folder and xmake wait the entire
+                    // sdk folder
+                    let compiler = c_cfg.get_compiler();
+                    let sdk = compiler.path().ancestors().nth(2).unwrap();
+                    cmd.arg(format!("--sdk={}", sdk.display()));
+                }
+                if self.cross.is_none() {
+                    let arch = get_xmake_arch(&plat, &triple);
+                    cmd.arg(format!("--cross={}-{}", arch, triple.os));
+                }
+                cmd.arg(format!("--toolchain={}", "cross"));
+            }
+        }
+        self.cache.plat = Some(plat.clone());
+
+        // Toolchain and SDK selected by the user, replacing the ones chosen above
+        if let Some(toolchain) = self.toolchain.as_ref() {
+            cmd.arg(format!("--toolchain={}", toolchain));
+        }
+        if let Some(sdk) = self.sdk.as_ref() {
+            cmd.arg(format!("--sdk={}", sdk.display()));
+        }
+        if let Some(cross) = self.cross.as_ref() {
+            cmd.arg(format!("--cross={}", cross));
+        }
+
+        if !self.runtimes.is_empty() {
+            cmd.arg(format!("--runtimes={}", self.runtimes.join(",")));
+        } else if plat == "windows" {
+            // Static CRT
+            let static_crt = self.static_crt.unwrap_or_else(|| self.get_static_crt());
+            // rustc doesn't support debug version of the CRT, so the `d`
+            // suffix is never appended even in debug mode.
+            let runtime = match static_crt {
+                true => "--runtimes=MT",
+                false => "--runtimes=MD",
+            };
+
+            cmd.arg(runtime);
+        } else if plat == "mingw" {
+            // The MSVC runtimes don't apply to the GNU toolchain, only the C++
+            // standard library can be linked statically.
+            let static_crt = self.static_crt.unwrap_or_else(|| self.get_static_crt());
+            let runtime = match static_crt {
+                true => "--runtimes=stdc++_static",
+                false => "--runtimes=stdc++_shared",
+            };
+
+            cmd.arg(runtime);
+        }
+
+        // Default kind of the targets
+        if let Some(arg) = self.target_kind.and_then(kind_arg) {
+            cmd.arg(arg);
+        }
+
+        // Policies
+        let mut policies = Vec::new();
+        if self.prefer_system_packages {
+            policies.push("package.fetch_only".to_string());
+        }
+        if self.offline {
+            policies.push("network.mode:private".to_string());
+        }
+        if self.lockfile || self.frozen {
+            policies.push("package.requires_lock".to_string());
+        }
+        let exclusive = [Sanitizer::Address, Sanitizer::Thread, Sanitizer::Memory];
+        if exclusive
+            .iter()
+            .filter(|s| self.sanitizers.contains(s))
+            .count()
+            > 1
+        {
+            fail("the address, thread and memory sanitizers can't be combined");
+        }
+        for sanitizer in self.sanitizers.iter() {
+            policies.push(format!("build.sanitizer.{}", sanitizer.name()));
+        }
+        if !policies.is_empty() {
+            cmd.arg(format!("--policies={}", policies.join(",")));
+        }
+
+        // Compilation mode: release, debug...
+        let mode = self.get_mode();
+        cmd.arg("-m").arg(mode);
+
+        // Option
+        let cxflags = self.compiler_flags(&plat);
+        cmd.args(self.option_args(&cxflags));
+
+        // Skip the configuration if nothing changed since the last one
+        let fingerprint = fingerprint(&cmd, self.cache.xmake_version);
+        let fingerprint_file = dst.join("xmakers").join("config.fingerprint");
+        if self.configuration_unchanged(&fingerprint, &fingerprint_file, &build_dir) {
+            debug_line(
+                self.verbose_commands,
+                "skipping xmake configure: configuration unchanged",
+            );
+            return;
+        }
+
+        match cmd.run() {
+            Ok(stdout) => self
+                .cache
+                .installed_packages
+                .extend(parse_installed_packages(&stdout)),
+            Err(e) => fail(&e.to_string()),
+        }
+
+        if let Err(e) = fs::create_dir_all(dst.join("xmakers"))
+            .and_then(|_| fs::write(&fingerprint_file, fingerprint))
+        {
+            self.warn(&format!(
+                "failed to write the configuration fingerprint: {}",
+                e
+            ));
+        }
+    }
+
+    /// Returns the compiler flags added for the xmake platform `plat`, from
+    /// the Rust profile and the options of the build.
+    fn compiler_flags(&self, plat: &str) -> Vec<&'static str> {
+        // Reduced debug information, xmake only knows about full symbols
+        let mut cxflags = Vec::new();
+        let debug_info = get_debug_info(&self.getenv_or("DEBUG", "false"));
+        let user_flags = self.options.iter().any(|(k, _)| k == "cxflags");
+        if self.mode.is_none()
+            && !user_flags
+            && !matches!(plat, "windows" | "wasm")
+            && matches!(debug_info, DebugInfo::Limited | DebugInfo::LineTables)
+        {
+            cxflags.push("-g1");
+        }
+
+        // Position independent code, needed to link static libraries in a cdylib
+        if self.pic && !matches!(plat, "windows" | "mingw" | "wasm") {
+            cxflags.push("-fPIC");
+        }
+        cxflags
+    }
+
+    /// Returns the `--key=value` arguments of the options, adding the compiler
+    /// flags `cxflags` to the ones set by the user.
+    fn option_args(&self, cxflags: &[&str]) -> Vec<OsString> {
+        let mut args = Vec::new();
+        let mut cxflags = cxflags.join(" ");
+        for (key, val) in self.options.iter() {
+            let mut val = val.clone();
+            // Only the last flags are kept by xmake, so merge them with the user ones
+            if key == "cxflags" && !cxflags.is_empty() {
+                val.push(" ");
+                val.push(&cxflags);
+                cxflags.clear();
+            }
+            args.push(option_arg(key, &val));
+        }
+        if !cxflags.is_empty() {
+            args.push(option_arg(OsStr::new("cxflags"), OsStr::new(&cxflags)));
+        }
+        // xmake options are global, so only pass the ones of the built targets
+        for (target, key, val) in self.target_options.iter() {
+            if self.targets.is_empty() || self.targets.contains(target) {
+                args.push(option_arg(key, val));
+            }
+        }
+        args
+    }
+
+    /// Returns whether the configure step can be skipped, the `fingerprint` of
+    /// the command matching the one saved in `fingerprint_file` by the last
+    /// configuration using `build_dir`.
+    fn configuration_unchanged(
+        &self,
+        fingerprint: &str,
+        fingerprint_file: &Path,
+        build_dir: &Path,
+    ) -> bool {
+        !self.always_configure
+            && fs::read_to_string(fingerprint_file).ok().as_deref() == Some(fingerprint)
+            && self.config_cache_exists(build_dir)
+    }
+
+    /// Returns whether xmake has a saved configuration for this project
+    /// using `buildir` as its build directory.
+    fn config_cache_exists(&self, buildir: &Path) -> bool {
+        // The configuration is saved in <buildir>/.xmake/<host>/<arch>/xmake.conf,
+        // see XMAKE_CONFIGDIR in `xmake_command`
+        let hosts = fs::read_dir(buildir.join(".xmake"));
+        let buildir = buildir.display().to_string();
+        let escaped = buildir.replace('\\', "\\\\");
+        let Ok(hosts) = hosts else {
+            return false;
+        };
+        hosts
+            .flatten()
+            .filter_map(|host| fs::read_dir(host.path()).ok())
+            .flat_map(|archs| archs.flatten())
+            .filter_map(|arch| fs::read_to_string(arch.path().join("xmake.conf")).ok())
+            .any(|conf| conf.contains(&buildir) || conf.contains(&escaped))
+    }
+
+    /// Generates the project building the package of [`Config::from_package`]
+    /// in the directory returned by [`Config::get_project_dir`].
+    fn generate_package_project(&mut self) {
+        let package = self.package.clone().unwrap();
+        let mut requires = vec![(package, self.package_configs.clone())];
+        requires.extend(self.requires.iter().cloned());
+
+        let dst = self.get_project_dir();
+        if let Err(e) = fs::create_dir_all(&dst)
+            .and_then(|_| fs::write(dst.join("xmake.lua"), package_project(&requires)))
+        {
+            fail(&format!("failed to generate the package project: {}", e));
+        }
+    }
+
+    /// Returns the directory of the xmake project to build, the generated one
+    /// for a configuration created with [`Config::from_package`].
+    fn get_project_dir(&self) -> PathBuf {
+        match self.package {
+            Some(_) => self.get_out_dir().join("xmakers").join("package"),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Generates the compile commands of the project at `path`.
+    fn generate_compile_commands(&mut self, path: &Path) {
+        // xmake always names the file compile_commands.json in the given directory
+        let dir = path.parent().unwrap_or(path);
+        let mut cmd = self.xmake_command();
+        cmd.task("project")
+            .arg("-k")
+            .arg("compile_commands")
+            .arg(dir);
+        cmd.execute();
+
+        let generated = dir.join("compile_commands.json");
+        if generated != path {
+            if let Err(e) = fs::rename(&generated, path) {
+                fail(&format!("failed to move {}: {}", generated.display(), e));
+            }
+        }
+    }
+
+    /// Adds the package repositories to the project.
+    fn add_repos(&mut self) {
+        for (name, url, branch) in self.repos.clone() {
+            let mut cmd = self.xmake_command();
+            cmd.task("repo").arg("--add").arg(name).arg(url);
+            if let Some(branch) = branch {
+                cmd.arg(branch);
+            }
+            cmd.execute();
+        }
+    }
+
+    /// Installs the packages required by the project.
+    fn require(&mut self) {
+        let mut cmd = self.xmake_command();
+        cmd.task("require");
+        if !self.offline {
+            cmd.arg("--yes");
+        }
+        self.run_require(cmd);
+    }
+
+    /// Returns the commands installing the packages of
+    /// [`Config::require_with_configs`], the generated package project
+    /// already requires them.
+    fn requires_commands(&mut self) -> Vec<XmakeCommand> {
+        if self.package.is_some() {
+            return Vec::new();
+        }
+        let mut commands = Vec::new();
+        for (package, configs) in self.requires.clone() {
+            let mut cmd = self.xmake_command();
+            cmd.task("require");
+            if !self.offline {
+                cmd.arg("--yes");
+            }
+            cmd.arg(format!("--extra={{configs = {}}}", lua_configs(&configs)))
+                .arg(package);
+            commands.push(cmd);
+        }
+        commands
+    }
+
+    /// Runs the `xmake require` command `cmd`, recording the installed packages.
+    fn run_require(&mut self, mut cmd: XmakeCommand) {
+        match cmd.run() {
+            Ok(stdout) => self
+                .cache
+                .installed_packages
+                .extend(parse_installed_packages(&stdout)),
+            Err(e) => fail(&e.to_string()),
+        }
+    }
+
+    /// Install target in OUT_DIR.
+    fn install(&mut self) -> PathBuf {
+        let dst = self.get_out_dir();
+        self.install_to(&dst)
+    }
+
+    /// Installs the built targets into `prefix`, returning it.
+    ///
+    /// The libraries and headers are installed in the `lib` and `include`
+    /// directories of `prefix` unless configured otherwise with
+    /// [`Config::install_libdir`] and [`Config::install_includedir`].
+    /// [`Config::build`] already installs into the output directory, this is
+    /// meant to install the built project elsewhere, for example to hand it
+    /// off to packaging.
+    pub fn install_to<P: AsRef<Path>>(&mut self, prefix: P) -> PathBuf {
+        let dst = env::current_dir().unwrap().join(prefix);
+
+        let mut cmd = self.xmake_command();
+        cmd.task("install");
+        cmd.arg("-o").arg(&dst);
+
+        if self.targets.is_empty() {
+            if let Some(group) = self.group.as_ref() {
+                cmd.arg(format!("--group={}", group));
+            } else if self.all_targets {
+                cmd.arg("--all");
+            }
+            cmd.execute();
+        }
+        for target in self.targets.iter() {
+            let mut cmd = cmd.clone();
+            cmd.arg(target_name(target)).execute();
+        }
+
+        // xmake always installs in lib and include
+        let renames = [
+            ("lib", self.install_libdir.clone()),
+            ("include", self.install_includedir.clone()),
+        ];
+        for (from, to) in renames {
+            let (from, to) = (dst.join(from), dst.join(to));
+            if from == to || !from.exists() {
+                continue;
+            }
+            let _ = fs::remove_dir_all(&to);
+            if let Err(e) =
+                fs::create_dir_all(to.parent().unwrap()).and_then(|_| fs::rename(&from, &to))
+            {
+                fail(&format!(
+                    "failed to move {} to {}: {}",
+                    from.display(),
+                    to.display(),
+                    e
+                ));
+            }
+        }
+
+        // The import libraries of the DLLs are linked against, they must be
+        // in the library directory even if xmake installed them next to the DLLs.
+        let plat = self.cache.plat.as_deref().unwrap_or_default();
+        if matches!(plat, "windows" | "mingw") {
+            self.move_import_libs(&dst.join("bin"), &dst.join(&self.install_libdir));
+        }
+        dst
+    }
+
+    /// Copies the debug symbols `paths` to `dst`, returning the installed
+    /// files.
+    fn install_debug_files(&self, paths: &[PathBuf], dst: &Path) -> Vec<PathBuf> {
+        let build_dir = self.get_build_dir();
+        let mut installed = Vec::new();
+        for path in paths
+            .iter()
+            .filter(|p| p.starts_with(&build_dir) && !p.starts_with(dst))
+        {
+            let to = dst.join(path.file_name().unwrap());
+            let _ = fs::remove_dir_all(&to);
+            match copy_dir_all(path, &to) {
+                Ok(()) => installed.push(to),
+                Err(e) => self.warn(&format!("failed to install {}: {}", path.display(), e)),
+            }
+        }
+        installed
+    }
+
+    /// Moves the import libraries, `foo.lib` or `libfoo.dll.a`, from `from`
+    /// to the directory `to`.
+    fn move_import_libs(&self, from: &Path, to: &Path) {
+        let Ok(entries) = fs::read_dir(from) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let name = file_name.to_string_lossy();
+            if !(name.ends_with(".lib") || name.ends_with(".dll.a")) {
+                continue;
+            }
+            let to = to.join(&file_name);
+            let _ = fs::remove_file(&to);
+            if let Err(e) =
+                fs::create_dir_all(to.parent().unwrap()).and_then(|_| fs::rename(entry.path(), &to))
+            {
+                fail(&format!(
+                    "failed to move {} to {}: {}",
+                    entry.path().display(),
+                    to.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Checks that the installed xmake is recent enough for the crate.
+    fn check_version(&mut self) {
+        let version = match self.xmake_version() {
+            Some(version) => *version,
+            None => fail("failed to get the version of xmake, is `xmake` installed?"),
+        };
+        if let Err(e) = check_minimum_version(version, self.minimum_version) {
+            fail(&e);
+        }
+        self.cache.xmake_version = Some(version);
+    }
+
+    /// Returns the output directory, `$OUT_DIR` by default, or a directory
+    /// of the temporary directory outside of cargo.
+    fn get_out_dir(&self) -> PathBuf {
+        if let Some(out_dir) = self.out_dir.clone() {
+            return out_dir;
+        }
+        match self.getenv_os("OUT_DIR") {
+            Some(out_dir) => PathBuf::from(out_dir),
+            None if !self.cargo_metadata => {
+                let mut hasher = DefaultHasher::new();
+                self.path.hash(&mut hasher);
+                env::temp_dir()
+                    .join("xmakers")
+                    .join(format!("{:016x}", hasher.finish()))
+            }
+            None => fail("environment variable `OUT_DIR` not defined"),
+        }
+    }
+
+    /// Returns the directory in which xmake builds the project, the output
+    /// directory unless configured otherwise.
+    fn get_build_dir(&self) -> PathBuf {
+        let build_dir = if let Some(dir) = self.build_dir.as_ref() {
+            dir.clone()
+        } else if self.shared_build_cache {
+            // The build cache is kept next to the build directories of the
+            // profile, in target/<profile>, which `cargo clean -p` keeps.
+            let out_dir = self.get_out_dir();
+            let profile_dir = match self.getenv_os("OUT_DIR") {
+                Some(_) => out_dir.ancestors().nth(3).unwrap_or(&out_dir),
+                None => &out_dir,
+            };
+
+            let mut hasher = DefaultHasher::new();
+            self.hash_configuration(&mut hasher);
+            profile_dir
+                .join("xmake-build-cache")
+                .join(format!("{:016x}", hasher.finish()))
+        } else {
+            return self.get_out_dir();
+        };
+
+        // Each architecture of a universal build needs its own build directory
+        match self.build_arch.as_ref() {
+            Some(arch) => build_dir.join(arch),
+            None => build_dir,
+        }
+    }
+
+    /// Hashes the settings used to configure the project, so the builds
+    /// configured differently don't share a build directory.
+    fn hash_configuration(&self, hasher: &mut DefaultHasher) {
+        self.path.hash(hasher);
+        self.package.hash(hasher);
+        self.package_configs.hash(hasher);
+        self.requires.hash(hasher);
+        self.repos.hash(hasher);
+        self.get_target().hash(hasher);
+        self.get_mode().hash(hasher);
+        self.getenv("DEBUG").hash(hasher);
+        self.options.hash(hasher);
+        self.target_options.hash(hasher);
+        self.target_kind.hash(hasher);
+        self.env.hash(hasher);
+        self.env_remove.hash(hasher);
+        self.toolchain.hash(hasher);
+        self.sdk.hash(hasher);
+        self.cross.hash(hasher);
+        self.runtimes.hash(hasher);
+        self.static_crt.hash(hasher);
+        self.cpp_link_stdlib.hash(hasher);
+        self.pic.hash(hasher);
+        self.sanitizers.hash(hasher);
+        self.prefer_system_packages.hash(hasher);
+        self.offline.hash(hasher);
+        self.lockfile.hash(hasher);
+        self.xmake_path.hash(hasher);
+        self.global_repo_dir.hash(hasher);
+    }
+
+    /// Writes the header `path` including every installed header.
+    fn write_wrapper_header(&self, path: &Path) {
+        let includedir = self.get_out_dir().join(&self.install_includedir);
+        let mut headers = Vec::new();
+        find_headers(&includedir, &mut headers);
+        headers.sort();
+        headers.dedup();
+
+        let mut content =
+            String::from("// Generated by xmake-rs, includes every installed header\n");
+        for header in headers {
+            let Ok(relative) = header.strip_prefix(&includedir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            content.push_str(&format!("#include <{}>\n", relative));
+        }
+        if let Err(e) = fs::write(path, content) {
+            fail(&format!("failed to write {}: {}", path.display(), e));
+        }
+    }
+
+    /// Copies the installed shared libraries to the profile directory of the
+    /// target directory, next to the binaries.
+    fn copy_dylibs_to_profile_dir(&self) {
+        // There are no binaries to copy the libraries next to outside of cargo
+        let Some(out_dir) = self.getenv_os("OUT_DIR").map(PathBuf::from) else {
+            return;
+        };
+        // OUT_DIR is target/<profile>/build/<crate>-<hash>/out
+        let Some(profile_dir) = out_dir.ancestors().nth(3) else {
+            return;
+        };
+
+        let installdir = self.get_out_dir();
+        let dirs = [
+            installdir.join(&self.install_libdir),
+            installdir.join("bin"),
+        ];
+        for entry in dirs.iter().filter_map(|d| fs::read_dir(d).ok()).flatten() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let file_name = entry.file_name();
+            let name = file_name.to_string_lossy();
+            let shared = name.ends_with(".dll")
+                || name.ends_with(".dylib")
+                || name.ends_with(".so")
+                || name.contains(".so.");
+            if !shared {
+                continue;
+            }
+
+            let from = entry.path();
+            let to = profile_dir.join(&file_name);
+            let _ = fs::remove_file(&to);
+            // Keep the symlinks of the versioned libraries, like libfoo.so -> libfoo.so.1
+            #[cfg(unix)]
+            let result = match fs::read_link(&from) {
+                Ok(link) => std::os::unix::fs::symlink(link, &to),
+                Err(_) => fs::copy(&from, &to).map(|_| ()),
+            };
+            #[cfg(not(unix))]
+            let result = fs::copy(&from, &to).map(|_| ());
+
+            match result {
+                Ok(()) => self.cargo_directive(&format!("rerun-if-changed={}", from.display())),
+                Err(e) => self.warn(&format!(
+                    "failed to copy {} to {}: {}",
+                    from.display(),
+                    to.display(),
+                    e
+                )),
+            }
+        }
+    }
+
+    /// Locks the project directory, as xmake saves its configuration inside,
+    /// so that the build scripts of several crates building the same project
+    /// don't run xmake at the same time.
+    ///
+    /// The lock is released when the returned file is dropped.
+    fn lock_project(&self) -> Option<File> {
+        if !self.locking {
+            return None;
+        }
+        let project_dir = self.get_project_dir();
+        let path = fs::canonicalize(&project_dir).unwrap_or(project_dir);
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        let lock_file = env::temp_dir().join(format!("xmakers-{:016x}.lock", hasher.finish()));
+
+        let lock = File::create(&lock_file).and_then(|file| lock_exclusive(&file).map(|_| file));
+        match lock {
+            Ok(file) => Some(file),
+            Err(e) => {
+                self.warn(&format!("failed to lock {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    /// Locks the build directory when it is outside of the output directory,
+    /// as it may be shared with other cargo processes.
+    ///
+    /// The lock is released when the returned file is dropped.
+    fn lock_build_dir(&self) -> Option<File> {
+        if self.build_dir.is_none() && !self.shared_build_cache {
+            return None;
+        }
+        let build_dir = self.get_build_dir();
+        let lock = fs::create_dir_all(&build_dir)
+            .and_then(|_| File::create(build_dir.join(".xmakers.lock")))
+            .and_then(|file| lock_exclusive(&file).map(|_| file));
+        match lock {
+            Ok(file) => Some(file),
+            Err(e) => {
+                self.warn(&format!("failed to lock {}: {}", build_dir.display(), e));
+                None
+            }
+        }
+    }
+
+    fn get_static_crt(&self) -> bool {
+        let feature = self.getenv("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+        feature.contains("crt-static")
+    }
+
+    /// Returns the mode registered with [`Config::mode_for_profile`] for the
+    /// current Cargo profile.
+    fn get_profile_mode(&self) -> Option<&str> {
+        if self.profile_modes.is_empty() {
+            return None;
+        }
+        // OUT_DIR is target/<profile>/build/<crate>-<hash>/out, PROFILE only
+        // tells debug from release
+        let out_dir = self.getenv_os("OUT_DIR").map(PathBuf::from);
+        let profile = out_dir
+            .as_deref()
+            .and_then(|dir| dir.ancestors().nth(3)?.file_name()?.to_str())
+            .map(str::to_string)
+            .or_else(|| self.getenv("PROFILE"))?;
+        self.profile_modes
+            .iter()
+            .find(|(p, _)| *p == profile)
+            .map(|(_, mode)| mode.as_str())
+    }
+
+    /// Return xmake mode, mapped from the Cargo profile or inferred from its
+    /// settings.
+    ///
+    /// * if `opt-level=0` then `debug`,
+    /// * if `opt-level={1,2,3}` and:
+    ///   * `debug=false` then `release`
+    ///   * otherwise `releasedbg`
+    /// * if `opt-level={s,z}` then `minsizerel`
+    fn get_mode(&self) -> &str {
+        if let Some(profile) = self.mode.as_ref() {
+            profile
+        } else if let Some(mode) = self.get_profile_mode() {
+            mode
+        } else {
+            #[derive(PartialEq)]
+            enum RustProfile {
+                Debug,
+                Release,
+            }
+            #[derive(PartialEq, Debug)]
+            enum OptLevel {
+                Debug,
+                Release,
+                Size,
+            }
+
+            let rust_profile = match &self.getenv_or("PROFILE", "release")[..] {
+                "debug" => RustProfile::Debug,
+                "release" | "bench" => RustProfile::Release,
+                unknown => {
+                    eprintln!(
+                        "Warning: unknown Rust profile={}; defaulting to a release build.",
+                        unknown
+                    );
+                    RustProfile::Release
+                }
+            };
+
+            let opt_level = match &self.getenv_or("OPT_LEVEL", "3")[..] {
+                "0" => OptLevel::Debug,
+                "1" | "2" | "3" => OptLevel::Release,
+                "s" | "z" => OptLevel::Size,
+                unknown => {
+                    let default_opt_level = match rust_profile {
+                        RustProfile::Debug => OptLevel::Debug,
+                        RustProfile::Release => OptLevel::Release,
+                    };
+                    eprintln!(
+                        "Warning: unknown opt-level={}; defaulting to a {:?} build.",
+                        unknown, default_opt_level
+                    );
+                    default_opt_level
+                }
+            };
+
+            let debug_info = get_debug_info(&self.getenv_or("DEBUG", "false")) != DebugInfo::None;
+
+            match (opt_level, debug_info) {
+                (OptLevel::Debug, _) if self.prefer_check_mode => "check",
+                (OptLevel::Debug, _) => "debug",
+                (OptLevel::Release, false) => "release",
+                (OptLevel::Release, true) => "releasedbg",
+                (OptLevel::Size, _) => "minsizerel",
+            }
+        }
+    }
+
+    /// Returns an xmake command with the same environment, verbosity and
+    /// project directory as this configuration, to run arbitrary tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use xmake::Config;
+    ///
+    /// let targets = Config::new("libfoo")
+    ///     .command()
+    ///     .task("show")
+    ///     .args(["-l", "targets"])
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn command(&mut self) -> XmakeCommand {
+        let mut cmd = self.xmake_command();
+        cmd.log = None;
+        cmd.echo = true;
+        cmd.progress_interval = None;
+        cmd
+    }
+
+    fn xmake_command(&mut self) -> XmakeCommand {
+        let (program, program_args) = self.xmake_executable();
+        let mut cmd = XmakeCommand::with_program(program, program_args);
+        cmd.project_dir(self.get_project_dir());
+        cmd.verbosity = self.verbosity;
+
+        // The output is only forwarded when verbose, it is always logged
+        cmd.log = Some(self.get_out_dir().join("xmake-build.log"));
+        let env_verbose = self
+            .getenv_os("XMAKERS_VERBOSE")
+            .is_some_and(|v| v == "1" || v == "true");
+        cmd.echo = self.verbosity > 0 || (env_verbose && !self.quiet);
+        cmd.progress_interval = Some(self.progress_interval)
+            .filter(|i| !i.is_zero() && !self.quiet && self.cargo_metadata);
+        cmd.quiet = self.quiet;
+        cmd.cancel = self.cancel.clone();
+        cmd.verbose_commands = self.verbose_commands;
+
+        // Keep the configuration out of the project directory, which cargo
+        // watches, so configuring doesn't rerun the build script
+        cmd.env("XMAKE_CONFIGDIR", self.get_build_dir());
+
+        // Add envs
+        for k in self.env_remove.iter() {
+            cmd.env_remove(k);
+        }
+        for (k, v) in self.env.iter() {
+            cmd.env(k, v);
+        }
+
+        let allow_root = self
+            .allow_root
+            .unwrap_or_else(|| root_allowed_by(self.getenv("XMAKERS_ALLOW_ROOT")));
+        cmd.allow_root(allow_root);
+
+        if let Some(dir) = self.global_repo_dir.as_ref() {
+            cmd.env("XMAKE_GLOBALDIR", dir);
+        }
+        cmd
+    }
+
+    fn xmake_executable(&mut self) -> (OsString, Vec<OsString>) {
+        if let Some(path) = self.xmake_path.as_ref() {
+            return (path.clone().into_os_string(), Vec::new());
+        }
+        match self.getenv_os("XMAKE") {
+            Some(xmake) => split_program(&xmake),
+            None => xmake_program(),
+        }
+    }
+
+    /// Reads the environment variable `v`, only once per build.
+    ///
+    /// The build script is rerun when the variable changes.
+    fn getenv_os(&self, v: &str) -> Option<OsString> {
+        if let Some(val) = self.cache.env.borrow().get(v) {
+            return val.clone();
+        }
+        let r = env::var_os(v);
+        let line = match r.as_ref() {
+            Some(_) if is_sensitive(v) => format!("{} = <redacted>", v),
+            _ => format!("{} = {:?}", v, r),
+        };
+        trace_line(self.verbose_commands, &line);
+        self.cargo_directive(&format!("rerun-if-env-changed={}", v));
+        self.cache.env.borrow_mut().insert(v.to_string(), r.clone());
+        r
+    }
+
+    fn getenv(&self, v: &str) -> Option<String> {
+        self.getenv_os(v).and_then(|s| s.into_string().ok())
+    }
+
+    fn getenv_unwrap(&self, v: &str) -> String {
+        match self.getenv(v) {
+            Some(s) => s,
+            None => fail(&format!("environment variable `{}` not defined", v)),
+        }
+    }
+
+    /// Reads the variable `v` set by cargo, falling back to `default` outside
+    /// of cargo.
+    fn getenv_or(&self, v: &str, default: &str) -> String {
+        match self.getenv(v) {
+            Some(s) => s,
+            None if !self.cargo_metadata => default.to_string(),
+            None => self.getenv_unwrap(v),
+        }
+    }
+
+    /// Returns the host triple, the host of `rustc` outside of cargo.
+    fn get_host(&self) -> String {
+        if let Some(host) = self.host.clone() {
+            return host;
+        }
+        match self.getenv("HOST") {
+            Some(host) => host,
+            None if !self.cargo_metadata => match rustc_host() {
+                Some(host) => host.to_string(),
+                None => fail("the host triple couldn't be read from `rustc -vV`"),
+            },
+            None => self.getenv_unwrap("HOST"),
+        }
+    }
+
+    /// Returns the target triple, the host outside of cargo.
+    fn get_target(&self) -> String {
+        if let Some(target) = self.target_triple.clone() {
+            return target;
+        }
+        match self.getenv("TARGET") {
+            Some(target) => target,
+            None if !self.cargo_metadata => self.get_host(),
+            None => self.getenv_unwrap("TARGET"),
+        }
+    }
+
+    /// Returns the target, described by the `$CARGO_CFG_TARGET_*` variables
+    /// unless set with [`Config::target_triple`] or outside of cargo.
+    fn get_triple(&self) -> Triple {
+        let target = self.get_target();
+        if self.target_triple.is_some() {
+            Triple::parse(&target)
+        } else {
+            Triple::from_cfg(&target, |v| self.getenv(v))
+        }
+    }
+
+    /// Prints the cargo directive `directive`, unless disabled with
+    /// [`Config::cargo_metadata`].
+    fn cargo_directive(&self, directive: &str) {
+        if self.cargo_metadata {
+            for line in self.directive_lines(directive) {
+                println!("cargo:{}", line);
+            }
+        }
+    }
+
+    /// Returns the lines printed for the cargo directive `directive`.
+    ///
+    /// Cargo stops rerunning the build script when any file of the package
+    /// changes once a `rerun-if` directive is printed, so the project
+    /// directory is watched along with the first one. Cargo scans it
+    /// recursively, which also catches the added sources.
+    fn directive_lines(&self, directive: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        // A generated package project has nothing to watch
+        if directive.starts_with("rerun-if")
+            && self.package.is_none()
+            && !self.cache.project_watched.replace(true)
+        {
+            lines.push(format!("rerun-if-changed={}", self.path.display()));
+        }
+        lines.push(directive.to_string());
+        lines
+    }
+
+    /// Prints the warning `message`, to the standard error outside of cargo.
+    fn warn(&self, message: &str) {
+        if self.cargo_metadata {
+            println!("cargo:warning={}", message);
+        } else {
+            eprintln!("warning: {}", message);
+        }
+    }
+}
+
+/// Formats the xmake option `--key=value`, keeping non UTF-8 values intact.
+fn option_arg(key: &OsStr, value: &OsStr) -> OsString {
+    let mut arg = OsString::from("--");
+    arg.push(key);
+    arg.push("=");
+    arg.push(value);
+    arg
+}
+
+/// Joins `items` with commas, escaping the commas and backslashes they contain
+/// with a backslash, as expected by the lua scripts.
+fn join_escaped(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| item.replace('\\', "\\\\").replace(',', "\\,"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Splits `s` at its first `:` that isn't part of a `::`, which separates the
+/// namespaces of xmake.
+fn split_single_colon(s: &str) -> Option<(&str, &str)> {
+    let bytes = s.as_bytes();
+    (0..bytes.len())
+        .find(|&i| {
+            bytes[i] == b':' && bytes.get(i + 1) != Some(&b':') && (i == 0 || bytes[i - 1] != b':')
+        })
+        .map(|i| (&s[..i], &s[i + 1..]))
+}
+
+/// Returns the name of the target addressed by `address`, which can be
+/// qualified by the directory of the `xmake.lua` declaring it, like
+/// `sub/dir:target`.
+fn target_name(address: &str) -> &str {
+    split_single_colon(address).map_or(address, |(_, name)| name)
+}
+
+/// Returns the warning about the unknown option `option`, with the closest
+/// option of `known` if any.
+fn unknown_option_message(option: &str, known: &[String]) -> String {
+    let mut message = format!("unknown xmake option `{}`", option);
+    if let Some(suggestion) = closest_option(option, known) {
+        message.push_str(&format!(", did you mean `{}`?", suggestion));
+    }
+    message
+}
+
+/// Returns the option of `known` closest to the unknown option `option`, if
+/// any is close enough to be a typo.
+fn closest_option<'a>(option: &str, known: &'a [String]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|k| (edit_distance(option, k), k))
+        .filter(|(distance, k)| {
+            *distance <= 2.max(k.len() / 3) || (k.len() >= 3 && option.contains(k.as_str()))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, k)| k.as_str())
+}
+
+/// Returns the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Returns whether the environment variable `name` may hold a secret, whose
+/// value must not be printed.
+///
+/// The `_`-separated words of the name are compared, so `GITHUB_TOKEN` is
+/// sensitive but `AUTHOR` and `KEYBOARD_LAYOUT` aren't.
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    name.split('_').any(|word| {
+        matches!(
+            word,
+            "TOKEN"
+                | "SECRET"
+                | "PASSWORD"
+                | "PASSWD"
+                | "KEY"
+                | "APIKEY"
+                | "CREDENTIAL"
+                | "CREDENTIALS"
+                | "AUTH"
+        )
+    })
+}
+
+/// Looks recursively in `dir` for C and C++ headers, adding them to `headers`.
+fn find_headers(dir: &Path, headers: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            find_headers(&path, headers);
+            continue;
+        }
+        let ext = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+        if matches!(ext, "h" | "hh" | "hpp" | "hxx" | "h++") {
+            headers.push(path);
+        }
+    }
+}
+
+/// Looks recursively in `dir` for separate debug symbols, adding them to `paths`.
+fn find_debug_symbols(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let ext = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        match (is_dir, ext) {
+            (true, "dSYM") => paths.push(path),
+            (true, _) => find_debug_symbols(&path, paths),
+            (false, "dwo" | "dwp" | "pdb") => paths.push(path),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the names of the packages installed according to the output of
+/// xmake, from lines like `=> install zlib v1.3.1 .. ok`.
+fn parse_installed_packages(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().strip_prefix("=> install ")?;
+            line.trim_end().ends_with(" ok").then_some(())?;
+            line.split_whitespace().next().map(str::to_string)
+        })
+        .collect()
+}
+
+/// Formats the resolved `packages` as `resolved zlib 1.3.1 (installed), fmt`,
+/// marking the ones of `installed`.
+fn package_summary(packages: &[Package], installed: &[String]) -> String {
+    let packages: Vec<String> = packages
+        .iter()
+        .map(|package| {
+            let mut summary = package.name().to_string();
+            if let Some(version) = package.version() {
+                summary.push(' ');
+                summary.push_str(version);
+            }
+            if installed.iter().any(|name| name == package.name()) {
+                summary.push_str(" (installed)");
+            }
+            summary
+        })
+        .collect();
+    format!("resolved {}", packages.join(", "))
+}
+
+/// Returns whether the output of `xmake build` reports that something was
+/// compiled, linked or archived.
+fn did_build(stdout: &str) -> bool {
+    stdout.lines().any(|line| {
+        let Some((_, step)) = line.split_once("]: ") else {
+            return false;
+        };
+        let step = step.strip_prefix("cache ").unwrap_or(step);
+        ["compiling", "linking", "archiving"]
+            .iter()
+            .any(|s| step.starts_with(s))
+    })
+}
+
+/// Fails the build if a symbol is defined by several of the static libraries
+/// `archives`, listing the duplicated symbols.
+fn check_duplicate_symbols(archives: &[PathBuf]) {
+    let mut definitions: HashMap<String, &Path> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for archive in archives {
+        let output = match Command::new("nm")
+            .args(["-g", "--defined-only"])
+            .arg(archive)
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => return,
+        };
+        // The weak and common symbols can be defined several times
+        let mut symbols = HashSet::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let [_, kind, symbol] = line.split_whitespace().collect::<Vec<_>>()[..] {
+                if matches!(kind, "T" | "D" | "B" | "R" | "S") {
+                    symbols.insert(symbol.to_string());
+                }
+            }
+        }
+        for symbol in symbols {
+            match definitions.get(symbol.as_str()) {
+                Some(other) => duplicates.push(format!(
+                    "{} (in {} and {})",
+                    symbol,
+                    other.display(),
+                    archive.display()
+                )),
+                None => {
+                    definitions.insert(symbol, archive);
+                }
+            }
+        }
+    }
+    if !duplicates.is_empty() {
+        duplicates.sort();
+        fail(&format!(
+            "the static libraries can't be bundled, these symbols are defined several times:\n{}",
+            duplicates.join("\n")
+        ));
+    }
+}
+
+/// Copies `from` to `to`, recursively if it is a directory.
+fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_dir_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Computes a fingerprint of the program, task, arguments and environment of
+/// `cmd` and of the xmake `version`.
+fn fingerprint(cmd: &XmakeCommand, version: Option<Version>) -> String {
+    let mut hasher = DefaultHasher::new();
+    version.hash(&mut hasher);
+    cmd.program.hash(&mut hasher);
+    cmd.program_args.hash(&mut hasher);
+    cmd.task.hash(&mut hasher);
+    cmd.args.hash(&mut hasher);
+    cmd.envs.hash(&mut hasher);
+    cmd.envs_remove.hash(&mut hasher);
+    cmd.project_dir.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Number of lines of the log shown when a command fails.
+const LOG_TAIL_LINES: usize = 50;
+
+/// Errors that can occur while running an xmake command.
+#[derive(Debug)]
+pub enum Error {
+    /// The xmake executable couldn't be found.
+    NotFound(io::Error),
+    /// The command couldn't be spawned or waited on.
+    Io(io::Error),
+    /// The command exited unsuccessfully.
+    Failed {
+        /// The exit status of the command.
+        status: ExitStatus,
+        /// The log file of the command, if any.
+        log: Option<PathBuf>,
+        /// The last lines printed by the command.
+        output: Vec<String>,
+    },
+    /// The command was cancelled through [`BuildHandle::cancel`].
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(e) => {
+                write!(
+                    f,
+                    "failed to execute command: {}\nis `xmake` not installed?",
+                    e
+                )?;
+                write!(f, "\n\nxmake was searched in PATH and in:")?;
+                for path in install_locations() {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                write!(
+                    f,
+                    "\n\nSet the XMAKE environment variable to the path of the executable, \
+                     or see https://xmake.io/#/guide/installation to install it."
+                )
+            }
+            Error::Io(e) => write!(f, "failed to execute command: {}", e),
+            Error::Cancelled => write!(f, "the build was cancelled"),
+            Error::Failed {
+                status,
+                log,
+                output,
+            } => {
+                write!(f, "command did not execute successfully, got: {}", status)?;
+                match log {
+                    Some(log) => write!(f, "\n\nlast lines of {}:", log.display())?,
+                    None => write!(f, "\n\nlast lines of the output:")?,
+                }
+                for line in output {
+                    write!(f, "\n{}", line)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotFound(e) | Error::Io(e) => Some(e),
+            Error::Failed { .. } | Error::Cancelled => None,
+        }
+    }
+}
+
+/// Builder style xmake invocation, to run arbitrary xmake tasks.
+///
+/// [`Config::command`] returns one with the same environment, verbosity and
+/// project directory as the configuration.
+///
+/// # Examples
+///
+/// ```no_run
+/// use xmake::XmakeCommand;
+///
+/// let info = XmakeCommand::new()
+///     .task("require")
+///     .arg("--info")
+///     .arg("zlib")
+///     .run()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct XmakeCommand {
+    program: OsString,
+    program_args: Vec<OsString>,
+    task: Option<String>,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    envs_remove: Vec<OsString>,
+    project_dir: Option<PathBuf>,
+    verbosity: u8,
+    log: Option<PathBuf>,
+    echo: bool,
+    progress_interval: Option<Duration>,
+    report: fn(&str),
+    cancel: Option<CancelToken>,
+    allow_root: bool,
+    verbose_commands: bool,
+    quiet: bool,
+    on_line: Option<Arc<Mutex<LineCallback>>>,
+}
+
+/// Closure receiving the lines printed by a command, see
+/// [`XmakeCommand::on_line`].
+type LineCallback = dyn FnMut(&str) + Send;
+
+/// Output of a running command, shared between the stdout and stderr readers.
+struct CommandLog {
+    file: Option<File>,
+    tail: VecDeque<String>,
+    // The whole output, kept to be printed if the command fails
+    buffer: Option<Vec<String>>,
+    echo: bool,
+    progress: Option<u32>,
+}
+
+impl CommandLog {
+    fn write(&mut self, line: &str) {
+        if self.echo {
+            println!("{}", line);
+        }
+        if let Some(progress) = parse_progress(line) {
+            self.progress = Some(progress);
+        }
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+        if let Some(buffer) = self.buffer.as_mut() {
+            buffer.push(line.to_string());
+        }
+        if self.tail.len() == LOG_TAIL_LINES {
+            self.tail.pop_front();
+        }
+        self.tail.push_back(line.to_string());
+    }
+}
+
+/// What a command printed, split between the regular output and the output
+/// between the markers.
+struct CommandOutput {
+    stdout: String,
+    captured: Option<String>,
+}
+
+impl Default for XmakeCommand {
+    fn default() -> Self {
+        XmakeCommand::new()
+    }
+}
+
+impl XmakeCommand {
+    /// Creates a new command running the xmake executable, as found by
+    /// [`find_executable`].
+    pub fn new() -> XmakeCommand {
+        let (program, program_args) = xmake_program();
+        XmakeCommand::with_program(program, program_args)
+    }
+
+    fn with_program(program: OsString, program_args: Vec<OsString>) -> XmakeCommand {
+        XmakeCommand {
+            program,
+            program_args,
+            task: None,
+            args: Vec::new(),
+            envs: Vec::new(),
+            envs_remove: Vec::new(),
+            project_dir: None,
+            verbosity: 0,
+            log: None,
+            echo: true,
+            progress_interval: None,
+            report: |message| println!("cargo:warning={}", message),
+            cancel: None,
+            allow_root: root_allowed(),
+            verbose_commands: false,
+            quiet: false,
+            on_line: None,
+        }
+    }
+
+    /// Sets the xmake task to run, like `build` or `show`.
+    pub fn task(&mut self, task: &str) -> &mut XmakeCommand {
+        self.task = Some(task.to_string());
+        self
+    }
+
+    /// Adds an argument passed after the task.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut XmakeCommand {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Adds several arguments passed after the task.
+    pub fn args<I, S>(&mut self, args: I) -> &mut XmakeCommand
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    /// Sets an environment variable for the xmake process.
+    pub fn env<K, V>(&mut self, key: K, value: V) -> &mut XmakeCommand
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.envs
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Removes an environment variable inherited by the xmake process.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut XmakeCommand {
+        self.envs_remove.push(key.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the project directory, in which xmake is run.
+    pub fn project_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut XmakeCommand {
+        self.project_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets verbose output, passing `-v` to xmake.
+    pub fn verbose(&mut self, value: bool) -> &mut XmakeCommand {
+        self.verbosity = value as u8;
+        self
+    }
+
+    /// Allows xmake to run as root, by setting `XMAKE_ROOT=y`.
+    ///
+    /// This defaults to `true` when running as root on Unix, which can be
+    /// overridden with the `XMAKERS_ALLOW_ROOT` environment variable.
+    pub fn allow_root(&mut self, value: bool) -> &mut XmakeCommand {
+        self.allow_root = value;
+        self
+    }
+
+    /// Streams the lines printed by xmake on stdout to `f` as they come,
+    /// instead of collecting them in memory, for commands with a large output.
+    ///
+    /// The lines printed between the `__xmakers_start__` and
+    /// `__xmakers_end__` markers are streamed as well, without the markers.
+    /// [`XmakeCommand::run`] then returns an empty string.
+    pub fn on_line<F>(&mut self, f: F) -> &mut XmakeCommand
+    where
+        F: FnMut(&str) + Send + 'static,
+    {
+        self.on_line = Some(Arc::new(Mutex::new(f)));
+        self
+    }
+
+    /// Runs the command and returns its output.
+    ///
+    /// The output is also printed as the command runs.
+    pub fn run(&mut self) -> Result<String, Error> {
+        self.run_inner().map(|output| output.stdout)
+    }
+
+    /// Runs the command and returns what was printed between the
+    /// `__xmakers_start__` and `__xmakers_end__` markers if any, failing the
+    /// build script if the command fails.
+    fn execute(&mut self) -> Option<String> {
+        match self.run_inner() {
+            Ok(output) => output.captured,
+            Err(e) => fail(&e.to_string()),
+        }
+    }
+
+    /// Builds the process to spawn.
+    fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.program_args);
+        if let Some(task) = self.task.as_ref() {
+            cmd.arg(task);
+        }
+        match self.verbosity {
+            0 => {}
+            1 => {
+                cmd.arg("-v");
+            }
+            _ => {
+                cmd.arg("-vD");
+            }
+        }
+        cmd.args(&self.args);
+        if self.allow_root {
+            cmd.env("XMAKE_ROOT", "y");
+        }
+        for key in self.envs_remove.iter() {
+            cmd.env_remove(key);
+        }
+        for (key, value) in self.envs.iter() {
+            cmd.env(key, value);
+        }
+        if let Some(dir) = self.project_dir.as_ref() {
+            cmd.current_dir(dir);
+            cmd.env("XMAKE_PROJECT_DIR", dir);
+        }
+        cmd
+    }
+
+    fn run_inner(&mut self) -> Result<CommandOutput, Error> {
+        let mut cmd = self.to_command();
+        debug_line(self.verbose_commands, &format!("running: {:?}", cmd));
+        let mut file = self
+            .log
+            .as_ref()
+            .and_then(|path| OpenOptions::new().create(true).append(true).open(path).ok());
+        if let Some(file) = file.as_mut() {
+            let _ = writeln!(file, "running: {:?}", cmd);
+        }
+        let log = Arc::new(Mutex::new(CommandLog {
+            file,
+            tail: VecDeque::new(),
+            buffer: (self.quiet && !self.echo).then(Vec::new),
+            echo: self.echo,
+            progress: None,
+        }));
+
+        // Spawn xmake in its own process group, so the compilers it runs are
+        // killed along with it when cancelled.
+        #[cfg(unix)]
+        if self.cancel.is_some() {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(Error::Cancelled);
+        }
+        let mut child = match cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(Error::NotFound(e)),
+            Err(e) => return Err(Error::Io(e)),
+        };
+        if let Some(cancel) = self.cancel.as_ref() {
+            cancel.register(&child);
+        }
+
+        // Report that the command is still running, otherwise cargo looks frozen
+        // during long builds.
+        let (done, heartbeat) = mpsc::channel::<()>();
+        let heartbeat = self.progress_interval.map(|interval| {
+            let log = Arc::clone(&log);
+            let report = self.report;
+            let start = Instant::now();
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = heartbeat.recv_timeout(interval) {
+                    let progress = log.lock().unwrap().progress;
+                    report(&heartbeat_message(progress, start.elapsed()));
+                }
+            })
+        });
+
+        let stderr = child.stderr.take().map(|stderr| {
+            let log = Arc::clone(&log);
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    log.lock().unwrap().write(&line);
+                }
+            })
+        });
+
+        let mut output = CommandOutput {
+            stdout: String::new(),
+            captured: None,
+        };
+        let mut capture: Option<String> = None;
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                // The markers may share their line with output that wasn't
+                // terminated by a newline, like the download progress of
+                // xmake on its first run.
+                let mut rest = line.as_str();
+                let mut split = false;
+                loop {
+                    let marker = match capture {
+                        None => START_MARKER,
+                        Some(_) => END_MARKER,
+                    };
+                    let (segment, next) = match rest.split_once(marker) {
+                        Some((segment, next)) => (segment, Some(next)),
+                        None => (rest, None),
+                    };
+                    split |= next.is_some();
+
+                    if !(split && segment.trim().is_empty()) {
+                        if capture.is_none() {
+                            log.lock().unwrap().write(segment);
+                        }
+                        match (self.on_line.as_ref(), capture.as_mut()) {
+                            (Some(on_line), _) => (on_line.lock().unwrap())(segment),
+                            (None, Some(captured)) => {
+                                captured.push_str(segment);
+                                captured.push('\n');
+                            }
+                            (None, None) => {
+                                output.stdout.push_str(segment);
+                                output.stdout.push('\n');
+                            }
+                        }
+                    }
+
+                    match next {
+                        Some(next) => {
+                            match capture.take() {
+                                Some(captured) => output.captured = Some(captured),
+                                None => capture = Some(String::new()),
+                            }
+                            rest = next;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        if let Some(stderr) = stderr {
+            let _ = stderr.join();
+        }
+        drop(done);
+        if let Some(heartbeat) = heartbeat {
+            let _ = heartbeat.join();
+        }
+
+        let status = child.wait().map_err(Error::Io)?;
+        if let Some(cancel) = self.cancel.as_ref() {
+            cancel.unregister();
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+        }
+        if !status.success() {
+            let log = log.lock().unwrap();
+            for line in log.buffer.iter().flatten() {
+                println!("{}", line);
+            }
+            return Err(Error::Failed {
+                status,
+                log: self.log.clone(),
+                output: log.tail.iter().cloned().collect(),
+            });
+        }
+        Ok(output)
+    }
+}
+
+/// Shared state used to cancel a build running in the background.
+#[derive(Clone, Default)]
+struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    child: Arc<Mutex<Option<ProcessTree>>>,
+}
+
+impl CancelToken {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn cancel(&self) {
+        let child = self.child.lock().unwrap();
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(tree) = child.as_ref() {
+            tree.kill();
+        }
+    }
+
+    /// Registers the running xmake process, killing it right away if the
+    /// build was cancelled while it was spawned.
+    fn register(&self, process: &Child) {
+        let mut child = self.child.lock().unwrap();
+        // Without the tree, a cancelled build still fails once xmake exits
+        *child = ProcessTree::new(process).ok();
+        if self.is_cancelled() {
+            if let Some(tree) = child.as_ref() {
+                tree.kill();
+            }
+        }
+    }
+
+    fn unregister(&self) {
+        *self.child.lock().unwrap() = None;
+    }
+}
+
+/// The xmake process and the processes it spawned, which are killed together.
+#[cfg(unix)]
+struct ProcessTree {
+    pgid: libc::pid_t,
+}
+
+#[cfg(unix)]
+impl ProcessTree {
+    /// Tracks `child`, which must run in its own process group.
+    fn new(child: &Child) -> io::Result<ProcessTree> {
+        // The id of the process group is the pid of its leader
+        Ok(ProcessTree {
+            pgid: child.id() as libc::pid_t,
+        })
+    }
+
+    fn kill(&self) {
+        // SAFETY: killpg has no memory safety requirements
+        unsafe {
+            libc::killpg(self.pgid, libc::SIGKILL);
+        }
+    }
+}
+
+/// The xmake process and the processes it spawned, which are killed together.
+///
+/// The process is assigned to a job object, which the processes it spawns
+/// join as well. Closing the job kills the processes left.
+#[cfg(windows)]
+struct ProcessTree {
+    job: job::Handle,
+}
+
+// SAFETY: the job handle can be used and closed from any thread
+#[cfg(windows)]
+unsafe impl Send for ProcessTree {}
+
+#[cfg(windows)]
+impl ProcessTree {
+    fn new(child: &Child) -> io::Result<ProcessTree> {
+        use std::os::windows::io::AsRawHandle;
+
+        // SAFETY: the job handle is owned by the returned tree, which closes
+        // it, and the limit information outlives the call using it.
+        unsafe {
+            let job = job::CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+            if job.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let tree = ProcessTree { job };
+
+            let mut info: job::ExtendedLimitInformation = std::mem::zeroed();
+            info.basic.limit_flags = job::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let set = job::SetInformationJobObject(
+                job,
+                job::JOB_OBJECT_EXTENDED_LIMIT_INFORMATION,
+                &mut info as *mut _ as *mut _,
+                std::mem::size_of::<job::ExtendedLimitInformation>() as u32,
+            );
+            if set == 0 || job::AssignProcessToJobObject(job, child.as_raw_handle()) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(tree)
+        }
+    }
+
+    fn kill(&self) {
+        // SAFETY: the handle is valid until the tree is dropped
+        unsafe {
+            job::TerminateJobObject(self.job, 1);
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        // SAFETY: the handle is owned by the tree and closed only once
+        unsafe {
+            job::CloseHandle(self.job);
+        }
+    }
+}
+
+/// The parts of the Win32 job objects API used to cancel a build.
+#[cfg(windows)]
+#[allow(non_snake_case)]
+mod job {
+    use std::ffi::c_void;
+
+    pub type Handle = *mut c_void;
+
+    pub const JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE: u32 = 0x2000;
+    pub const JOB_OBJECT_EXTENDED_LIMIT_INFORMATION: i32 = 9;
+
+    /// `JOBOBJECT_BASIC_LIMIT_INFORMATION`
+    #[repr(C)]
+    pub struct BasicLimitInformation {
+        pub per_process_user_time_limit: i64,
+        pub per_job_user_time_limit: i64,
+        pub limit_flags: u32,
+        pub minimum_working_set_size: usize,
+        pub maximum_working_set_size: usize,
+        pub active_process_limit: u32,
+        pub affinity: usize,
+        pub priority_class: u32,
+        pub scheduling_class: u32,
+    }
+
+    /// `JOBOBJECT_EXTENDED_LIMIT_INFORMATION`
+    #[repr(C)]
+    pub struct ExtendedLimitInformation {
+        pub basic: BasicLimitInformation,
+        pub io_info: [u64; 6],
+        pub process_memory_limit: usize,
+        pub job_memory_limit: usize,
+        pub peak_process_memory_used: usize,
+        pub peak_job_memory_used: usize,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateJobObjectW(attributes: *mut c_void, name: *const u16) -> Handle;
+        pub fn SetInformationJobObject(
+            job: Handle,
+            class: i32,
+            info: *mut c_void,
+            length: u32,
+        ) -> i32;
+        pub fn AssignProcessToJobObject(job: Handle, process: Handle) -> i32;
+        pub fn TerminateJobObject(job: Handle, exit_code: u32) -> i32;
+        pub fn CloseHandle(handle: Handle) -> i32;
+    }
+}
+
+/// Locks `file` exclusively, waiting for the other processes holding it.
+///
+/// The lock is released when the file is closed, including when the process
+/// exits without closing it.
+#[cfg(unix)]
+fn lock_exclusive(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    loop {
+        // SAFETY: the descriptor is owned by `file`, which outlives the call
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(());
+        }
+        let e = io::Error::last_os_error();
+        if e.kind() != ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+}
+
+/// Locks `file` exclusively, waiting for the other processes holding it.
+///
+/// The lock is released when the file is closed, including when the process
+/// exits without closing it.
+#[cfg(windows)]
+fn lock_exclusive(file: &File) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+
+    let mut overlapped = lock::Overlapped {
+        internal: 0,
+        internal_high: 0,
+        offset: 0,
+        offset_high: 0,
+        event: std::ptr::null_mut(),
+    };
+    // SAFETY: the handle is owned by `file`, which outlives the call, and the
+    // overlapped structure is only used by this synchronous call
+    let locked = unsafe {
+        lock::LockFileEx(
+            file.as_raw_handle(),
+            lock::LOCKFILE_EXCLUSIVE_LOCK,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    match locked {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Doesn't lock `file` on the platforms without file locks.
+#[cfg(not(any(unix, windows)))]
+fn lock_exclusive(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// The parts of the Win32 file locking API used to lock the projects.
+#[cfg(windows)]
+#[allow(non_snake_case)]
+mod lock {
+    use std::ffi::c_void;
+
+    pub const LOCKFILE_EXCLUSIVE_LOCK: u32 = 0x2;
+
+    /// `OVERLAPPED`
+    #[repr(C)]
+    pub struct Overlapped {
+        pub internal: usize,
+        pub internal_high: usize,
+        pub offset: u32,
+        pub offset_high: u32,
+        pub event: *mut c_void,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn LockFileEx(
+            file: *mut c_void,
+            flags: u32,
+            reserved: u32,
+            bytes_low: u32,
+            bytes_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+    }
+}
+
+/// A build running in the background, started by [`Config::spawn`].
+pub struct BuildHandle {
+    thread: JoinHandle<PathBuf>,
+    cancel: CancelToken,
+}
+
+impl BuildHandle {
+    /// Cancels the build, killing xmake and the compilers it is running.
+    ///
+    /// The build then fails with [`Error::Cancelled`] when joined.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Returns whether the build is finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the build to finish, returning the directory in which the
+    /// library was installed.
+    ///
+    /// A failed or cancelled build returns the panic payload of the build,
+    /// like [`std::thread::JoinHandle::join`].
+    pub fn join(self) -> thread::Result<PathBuf> {
+        self.thread.join()
+    }
+}
+
+/// Memory reserved for each compiler process when picking the number of jobs.
+const MEMORY_PER_JOB: u64 = 1536 * 1024 * 1024;
+
+/// Picks a number of jobs from the host's CPU count and available memory.
+fn auto_jobs() -> usize {
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    tune_jobs(cpus, available_memory())
+}
+
+/// Limits `cpus` jobs so that each one has [`MEMORY_PER_JOB`] bytes of
+/// `memory`, keeping at least one job.
+fn tune_jobs(cpus: usize, memory: Option<u64>) -> usize {
+    let cpus = cpus.max(1);
+    match memory {
+        Some(memory) => cpus.min((memory / MEMORY_PER_JOB).max(1) as usize),
+        None => cpus,
+    }
+}
+
+/// Returns the memory available on the host in bytes, if it can be queried.
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Returns the memory available on the host in bytes, if it can be queried.
+#[cfg(target_os = "macos")]
+fn available_memory() -> Option<u64> {
+    // The total memory, macOS doesn't expose the available one with sysctl
+    let output = Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output()
+        .ok()?;
+    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+}
+
+/// Returns the memory available on the host in bytes, if it can be queried.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn available_memory() -> Option<u64> {
+    None
+}
+
+/// Returns the message reporting that a command is still running after
+/// `elapsed`, with its last `progress`.
+fn heartbeat_message(progress: Option<u32>, elapsed: Duration) -> String {
+    let elapsed = elapsed.as_secs();
+    let elapsed = match elapsed {
+        0..=59 => format!("{}s", elapsed),
+        _ => format!("{}m", elapsed / 60),
+    };
+    match progress {
+        Some(progress) => format!(
+            "xmake: still building [{:>3}%] ({} elapsed)",
+            progress, elapsed
+        ),
+        None => format!("xmake: still building ({} elapsed)", elapsed),
+    }
+}
+
+/// Parses the progress printed by xmake at the start of a line, like `[ 42%]:`.
+fn parse_progress(line: &str) -> Option<u32> {
+    let progress = line.strip_prefix('[')?.split_once("%]")?.0;
+    progress.trim().parse().ok()
+}
+
+/// Returns whether xmake should be allowed to run as root, from the
+/// `XMAKERS_ALLOW_ROOT` environment variable or the effective user.
+fn root_allowed() -> bool {
+    root_allowed_by(env::var("XMAKERS_ALLOW_ROOT").ok())
+}
+
+/// Returns whether xmake should be allowed to run as root, given the value
+/// of `XMAKERS_ALLOW_ROOT`.
+fn root_allowed_by(value: Option<String>) -> bool {
+    match value {
+        Some(value) => !matches!(value.as_str(), "0" | "false"),
+        None => is_root(),
+    }
+}
+
+/// Returns whether the process runs as root.
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and can't fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Returns whether the process runs as root.
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+/// Returns the directories of the NDK `ndk` containing the libraries of the
+/// Rust `target`, the one of the API level `api` (`21` or `android-21`) first.
+/// Only the existing directories are returned.
+fn get_ndk_libdirs(ndk: &Path, target: &str, api: Option<&str>) -> Vec<PathBuf> {
+    let triple = match target.split('-').next() {
+        Some("aarch64") => "aarch64-linux-android",
+        Some("i686") => "i686-linux-android",
+        Some("x86_64") => "x86_64-linux-android",
+        Some(a) if a.starts_with("arm") || a.starts_with("thumb") => "arm-linux-androideabi",
+        _ => return Vec::new(),
+    };
+
+    // There is a single prebuilt toolchain, named after the host
+    let prebuilt = ndk.join("toolchains").join("llvm").join("prebuilt");
+    let host = fs::read_dir(prebuilt)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_dir());
+    let Some(host) = host else {
+        return Vec::new();
+    };
+
+    let libdir = host.join("sysroot").join("usr").join("lib").join(triple);
+    let mut dirs = Vec::new();
+    if let Some(api) = api {
+        dirs.push(libdir.join(api.trim_start_matches("android-")));
+    }
+    dirs.push(libdir);
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
+
+/// Returns the content of the `xmake.lua` requiring the packages `requires`
+/// with their configs, whose values are lua values.
+fn package_project(requires: &[(String, Vec<(String, String)>)]) -> String {
+    let mut project = String::new();
+    for (package, configs) in requires.iter() {
+        project += &format!(
+            "add_requires({}, {{configs = {}}})\n",
+            lua_string(package),
+            lua_configs(configs)
+        );
+    }
+    project += "\ntarget(\"xmakers_package\")\n    set_kind(\"phony\")\n";
+    for (package, _) in requires.iter() {
+        let name = package.split_whitespace().next().unwrap_or(package);
+        project += &format!("    add_packages({})\n", lua_string(name));
+    }
+    project
+}
+
+/// Returns the package configs `configs` as a lua table, the values being lua
+/// values.
+fn lua_configs(configs: &[(String, String)]) -> String {
+    let configs = configs
+        .iter()
+        .map(|(key, value)| format!("[{}] = {}", lua_string(key), value))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{{{}}}", configs)
+}
+
+/// Returns `s` as a quoted lua string, escaping the characters ending it.
+fn lua_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The oldest version of xmake supported by the crate.
+const MINIMUM_VERSION: Version = Version {
+    major: 2,
+    minor: 8,
+    patch: 5,
+};
+
+/// Checks that the xmake `version` is at least the one required by the crate
+/// and `minimum`, returning the error to report otherwise.
+fn check_minimum_version(version: Version, minimum: Option<Version>) -> Result<(), String> {
+    let minimum = MINIMUM_VERSION.max(minimum.unwrap_or_default());
+    if version < minimum {
+        return Err(format!(
+            "xmake {} was found, but {} or newer is required",
+            version, minimum
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the `--kind=` argument building the targets as `kind`, or `None`
+/// if xmake can't build this kind.
+fn kind_arg(kind: LinkKind) -> Option<String> {
+    let kind = match kind {
+        LinkKind::Static => "static",
+        LinkKind::Dynamic => "shared",
+        LinkKind::System | LinkKind::Framework | LinkKind::HeaderOnly | LinkKind::Unknown => {
+            return None
+        }
+    };
+    Some(format!("--kind={}", kind))
+}
+
+/// Returns the name to link a system library `name` reported by xmake with on
+/// the xmake platform `plat`, or `None` if it doesn't exist on the platform.
+fn get_syslink<'a>(plat: &str, name: &'a str) -> Option<&'a str> {
+    if plat != "windows" && plat != "mingw" {
+        return Some(name);
+    }
+
+    // Import libraries can be given with their extension on Windows
+    let name = name.strip_suffix(".lib").unwrap_or(name);
+    match name {
+        // Part of the C runtime on Windows
+        "pthread" | "dl" | "m" | "rt" if plat == "windows" => None,
+        _ => Some(name),
+    }
+}
+
+/// Amount of debug information requested by the Rust profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebugInfo {
+    None,
+    LineTables,
+    Limited,
+    Full,
+}
+
+/// Reads the debug information level of the Rust profile from the value of
+/// `DEBUG`, which can be a boolean or one of the values of the `debug` profile
+/// setting.
+fn get_debug_info(debug: &str) -> DebugInfo {
+    match debug {
+        "false" | "0" | "none" => DebugInfo::None,
+        "line-tables-only" | "line-directives-only" => DebugInfo::LineTables,
+        "1" | "limited" => DebugInfo::Limited,
+        "true" | "2" | "full" => DebugInfo::Full,
+        unknown => {
+            eprintln!("Warning: unknown debug={}; defaulting to `true`.", unknown);
+            DebugInfo::Full
+        }
+    }
+}
+
+/// Returns the `--plat` and `--arch` arguments of `xmake config` for the
+/// platform `plat` of the `triple`.
+///
+/// The architecture is `build_arch` if set, otherwise the one of the triple
+/// when cross compiling to a platform other than `cross`, xmake choosing the
+/// host one by default.
+fn plat_args(
+    plat: &str,
+    triple: &Triple,
+    cross_compiling: bool,
+    build_arch: Option<&str>,
+) -> Vec<String> {
+    let mut args = vec![format!("--plat={}", plat)];
+    let arch = match build_arch {
+        Some(arch) => Some(arch.to_string()),
+        None if cross_compiling && plat != "cross" => Some(get_xmake_arch(plat, triple)),
+        None => None,
+    };
+    args.extend(arch.map(|arch| format!("--arch={}", arch)));
+    args
+}
+
+/// Converts the Rust architecture of the `triple` to the xmake one of the
+/// platform `plat`.
+fn get_xmake_arch(plat: &str, triple: &Triple) -> String {
+    let little = triple.endian == "little";
+    match (plat, triple.arch.as_str()) {
+        ("android", a) if a.starts_with("armv7") || a.starts_with("thumbv7") => "armeabi-v7a",
+        ("android", "arm") => "armeabi",
+        ("android", "aarch64") => "arm64-v8a",
+        ("android", "i686") => "x86",
+        ("appletvos", "aarch64") => "arm64",
+        ("watchos", "arm64_32") => "armv7k",
+        ("watchos", "armv7k") => "armv7k",
+        ("iphoneos", "aarch64") => "arm64",
+        ("macosx", "aarch64") => "arm64",
+        ("windows", "i686") => "x86",
+        ("wasm", _) => "wasm32",
+        (_, "aarch64") => "arm64",
+        (_, "i686") => "i386",
+        // The extensions of the triples are not part of the xmake names
+        (_, a) if a.starts_with("riscv64") => "riscv64",
+        (_, a) if a.starts_with("riscv32") => "riscv32",
+        (_, "powerpc64") if little => "ppc64le",
+        (_, "powerpc64le") => "ppc64le",
+        (_, "powerpc64") => "ppc64",
+        (_, "powerpc") => "ppc",
+        (_, "s390x") => "s390x",
+        (_, "mips64" | "mips64el") if little => "mips64el",
+        (_, "mips64") => "mips64",
+        (_, "mips" | "mipsel") if little => "mipsel",
+        (_, "mips") => "mips",
+        (_, "loongarch64") => "loong64",
+        (_, a) => a,
+    }
+    .to_string()
+}
+
+/// Convert rust platform to xmake one, given the target env and family of the
+/// Rust target.
+///
+/// The env and family are empty when they are unknown, in which case an
+/// unknown platform falls back to `cross`.
+fn get_xmake_plat(triple: &Triple) -> Option<String> {
+    let family = triple.family.as_str();
+    // List of xmake platform https://github.com/xmake-io/xmake/tree/master/xmake/platforms
+    match triple.os.as_str() {
+        "windows" if triple.env == "gnu" => Some("mingw".to_string()),
+        "windows" => Some("windows".to_string()),
+        "linux" => Some("linux".to_string()),
+        "android" => Some("android".to_string()),
+        "androideabi" => Some("android".to_string()),
+        "emscripten" => Some("wasm".to_string()),
+        "macos" => Some("macosx".to_string()),
+        "ios" => Some("iphoneos".to_string()),
+        "tvos" => Some("appletvos".to_string()),
+        "fuchsia" => None,
+        "solaris" => None,
+        _ if family == "wasm" => Some("wasm".to_string()),
+        _ => Some("cross".to_string()),
+    }
+}
+
+/// A Rust target triple, like `x86_64-unknown-linux-gnu`, split into the
+/// parts used to choose the xmake platform and architecture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Triple {
+    /// The architecture as written in the triple, like `armv7` or `i686`.
+    arch: String,
+    /// The operating system, like `target_os`.
+    os: String,
+    /// The environment, like `target_env`.
+    env: String,
+    /// The family, like `target_family`, the first one if there are several.
+    family: String,
+    /// The endianness, like `target_endian`.
+    endian: String,
+}
+
+impl Triple {
+    /// Parses the Rust target `triple`.
+    ///
+    /// The parts are guessed from the triple alone, the `$CARGO_CFG_TARGET_*`
+    /// variables should be preferred when building for the target of cargo.
+    fn parse(triple: &str) -> Triple {
+        let parts: Vec<_> = triple.split('-').collect();
+        let arch = parts[0];
+        // Android triples also contain `linux`
+        let android = parts.iter().any(|part| part.starts_with("android"));
+        let os = parts
+            .iter()
+            .skip(1)
+            .find_map(|part| match *part {
+                _ if android => Some("android"),
+                "darwin" => Some("macos"),
+                "ios" | "tvos" | "watchos" | "windows" | "linux" | "emscripten" => Some(*part),
+                p if p.starts_with("wasi") => Some("wasi"),
+                "freebsd" | "netbsd" | "openbsd" | "dragonfly" | "solaris" | "fuchsia" => {
+                    Some(*part)
+                }
+                _ => None,
+            })
+            .unwrap_or("none");
+        // The last part is the environment, or the ABI like in `androideabi`
+        // and `ios-sim`.
+        let env = match parts.last().copied().unwrap_or_default() {
+            _ if parts.len() < 3 => "",
+            e if e.starts_with("gnu") => "gnu",
+            e if e.starts_with("msvc") => "msvc",
+            e if e.starts_with("musl") => "musl",
+            _ => "",
+        };
+        let family = match (arch, os) {
+            (_, "windows") => "windows",
+            (a, _) if a.starts_with("wasm") && os != "emscripten" => "wasm",
+            (_, "none") => "",
+            _ => "unix",
+        };
+        let endian = match arch {
+            "powerpc" | "powerpc64" | "s390x" | "sparc64" | "mips" | "mips64" => "big",
+            _ => "little",
+        };
+        Triple {
+            arch: arch.to_string(),
+            os: os.to_string(),
+            env: env.to_string(),
+            family: family.to_string(),
+            endian: endian.to_string(),
+        }
+    }
+
+    /// Parses the Rust target `triple`, with the parts read by `var` from the
+    /// `CARGO_CFG_TARGET_*` variables taking precedence.
+    ///
+    /// The architecture is always the one of the triple, as `target_arch`
+    /// doesn't tell `armv7` from `arm`.
+    fn from_cfg<F: Fn(&str) -> Option<String>>(triple: &str, var: F) -> Triple {
+        let mut parsed = Triple::parse(triple);
+        if let Some(os) = var("CARGO_CFG_TARGET_OS") {
+            parsed.os = os;
+            parsed.env = var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+            let family = var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+            parsed.family = family.split(',').next().unwrap_or_default().to_string();
+        }
+        if let Some(endian) = var("CARGO_CFG_TARGET_ENDIAN") {
+            parsed.endian = endian;
+        }
+        parsed
+    }
+}
+
+/// Prints the diagnostic `line` of the crate to stderr when `verbose` is set,
+/// and emits it as a `debug!` record with the `log` feature.
+fn debug_line(verbose: bool, line: &str) {
+    #[cfg(feature = "log")]
+    log::debug!("{}", line);
+    if verbose {
+        eprintln!("{}", line);
+    }
+}
+
+/// Same as [`debug_line`], with a `trace!` record.
+fn trace_line(verbose: bool, line: &str) {
+    #[cfg(feature = "log")]
+    log::trace!("{}", line);
+    if verbose {
+        eprintln!("{}", line);
+    }
+}
+
+fn fail(s: &str) -> ! {
+    panic!("\n{}\n\nbuild script failed, must exit now", s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Held by the tests reading or changing the environment variables of the
+    /// process.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Returns the environment variables set by `cmd` on the spawned process.
+    fn command_envs(cmd: &XmakeCommand) -> Vec<(String, Option<String>)> {
+        cmd.to_command()
+            .get_envs()
+            .map(|(key, value)| {
+                let value = value.map(|v| v.to_string_lossy().into_owned());
+                (key.to_string_lossy().into_owned(), value)
+            })
+            .collect()
+    }
+
+    /// Returns an empty directory of the temporary directory for the test
+    /// `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("xmakers-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn kind_is_forwarded() {
+        assert_eq!(kind_arg(LinkKind::Static).as_deref(), Some("--kind=static"));
+        assert_eq!(
+            kind_arg(LinkKind::Dynamic).as_deref(),
+            Some("--kind=shared")
+        );
+        assert_eq!(kind_arg(LinkKind::System), None);
+
+        let mut config = Config::new(".");
+        config.default_target_kind(LinkKind::Dynamic);
+        assert_eq!(config.target_kind, Some(LinkKind::Dynamic));
+    }
+
+    #[test]
+    fn default_target_kind_selects_the_built_artifact() {
+        // Needs a real xmake, which isn't installed on every machine
+        if find_executable().is_none() {
+            eprintln!("xmake wasn't found, the libraries aren't built");
+            return;
+        }
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-crate/libdouble");
+        let dir = test_dir("target-kind");
+        let extensions = |out: &Path| -> Vec<String> {
+            ["lib", "bin"]
+                .iter()
+                .filter_map(|sub| fs::read_dir(out.join(sub)).ok())
+                .flatten()
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    Some(path.extension()?.to_string_lossy().into_owned())
+                })
+                .collect()
+        };
+        let shared = |ext: &String| ["so", "dylib", "dll"].contains(&ext.as_str());
+
+        for kind in [LinkKind::Static, LinkKind::Dynamic] {
+            let out = dir.join(kind.to_string());
+            Config::new(&project)
+                .cargo_metadata(false)
+                .out_dir(&out)
+                .default_target_kind(kind)
+                .build();
+            let extensions = extensions(&out);
+            match kind {
+                LinkKind::Static => assert!(
+                    extensions.iter().any(|e| e == "a" || e == "lib")
+                        && !extensions.iter().any(shared),
+                    "{:?}",
+                    extensions
+                ),
+                _ => assert!(extensions.iter().any(shared), "{:?}", extensions),
+            }
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn link_given_twice_is_linked_once() {
+        let mut set = OrderedSet::new();
+        assert!(set.insert("foo"));
+        assert!(set.insert("bar"));
+        assert!(!set.insert("foo"));
+        assert_eq!(set.items, ["foo", "bar"]);
+
+        let info = BuildInfo {
+            links: vec![
+                Link::new("foo", LinkKind::Static),
+                Link::new("bar", LinkKind::Static),
+                Link::new("foo", LinkKind::Static),
+            ],
+            ..BuildInfo::default()
+        };
+        let (_, links) = info.collect_links(&LinkOptions::new("windows"));
+        let names: Vec<_> = links.items.iter().map(Link::name).collect();
+        assert_eq!(names, ["foo", "bar"]);
+    }
+
+    #[test]
+    fn version_parse() {
+        let parse = Version::parse;
+        assert_eq!(
+            parse("xmake v2.9.4+20240829, A cross-platform build utility"),
+            Some(Version::new(2, 9, 4))
+        );
+        assert_eq!(
+            parse("xmake v2.9.5+dev.478972cd9, A cross-platform build utility"),
+            Some(Version::new(2, 9, 5))
+        );
+        assert_eq!(
+            parse("xmake v2.9.5+HEAD.0db4fe6, A cross-platform build utility"),
+            Some(Version::new(2, 9, 5))
+        );
+        assert_eq!(
+            parse("first run message\nxmake v3.0.0+master.1\n"),
+            Some(Version::new(3, 0, 0))
+        );
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("xmake"), None);
+        assert_eq!(parse("xmake v2.9"), None);
+        assert_eq!(parse("xmake v2.x.4+dev"), None);
+        assert_eq!(parse("xmake v2.9.4.1"), None);
+    }
+
+    #[test]
+    fn minimum_version() {
+        let found = Version::new(2, 9, 4);
+        assert_eq!(check_minimum_version(found, None), Ok(()));
+        assert_eq!(
+            check_minimum_version(found, Some(Version::new(2, 9, 4))),
+            Ok(())
+        );
+        assert_eq!(
+            check_minimum_version(found, Some(Version::new(99, 0, 0))),
+            Err("xmake 2.9.4 was found, but 99.0.0 or newer is required".to_string())
+        );
+        // The version required by the crate can't be lowered
+        assert_eq!(
+            check_minimum_version(Version::new(2, 8, 1), Some(Version::new(2, 0, 0))),
+            Err("xmake 2.8.1 was found, but 2.8.5 or newer is required".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancel_kills_the_process_group() {
+        use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let token = CancelToken::default();
+        token.register(&child);
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+        let status = child.wait().unwrap();
+        assert!(token.is_cancelled());
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn registering_a_cancelled_build_kills_it() {
+        use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+        let token = CancelToken::default();
+        token.cancel();
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        token.register(&child);
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+    }
+
+    #[test]
+    fn package_project_is_escaped() {
+        assert_eq!(lua_string(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(lua_string("a\nb"), r#""a\nb""#);
+
+        let requires = vec![
+            (
+                "zlib 1.3".to_string(),
+                vec![("shared".to_string(), "true".to_string())],
+            ),
+            (r#"evil")os.exec("x"#.to_string(), Vec::new()),
+        ];
+        assert_eq!(
+            package_project(&requires),
+            r#"add_requires("zlib 1.3", {configs = {["shared"] = true}})
+add_requires("evil\")os.exec(\"x", {configs = {}})
+
+target("xmakers_package")
+    set_kind("phony")
+    add_packages("zlib")
+    add_packages("evil\")os.exec(\"x")
+"#
+        );
+    }
+
+    #[test]
+    fn requires_are_installed_with_their_configs() {
+        let requires = |configs: &[(&str, &str)]| {
+            let mut config = Config::new(".");
+            config
+                .out_dir(env::temp_dir().join("xmakers-test-requires"))
+                .require_with_configs("openssl 3.x", configs);
+            config
+                .requires_commands()
+                .into_iter()
+                .map(|cmd| (cmd.task, cmd.args))
+                .collect::<Vec<_>>()
+        };
+        let args = |extra: &str| {
+            let args = ["--yes", extra, "openssl 3.x"];
+            vec![(Some("require".into()), args.map(OsString::from).to_vec())]
+        };
+        assert_eq!(
+            requires(&[("shared", "false")]),
+            args(r#"--extra={configs = {["shared"] = false}}"#)
+        );
+        assert_eq!(
+            requires(&[("shared", "true"), ("no_asm", "true")]),
+            args(r#"--extra={configs = {["shared"] = true, ["no_asm"] = true}}"#)
+        );
+        assert_eq!(requires(&[]), args("--extra={configs = {}}"));
+
+        // The generated package project requires them itself
+        let mut config = Config::from_package("libcurl");
+        config.require_with_configs("openssl", &[("shared", "false")]);
+        assert!(config.requires_commands().is_empty());
+    }
+
+    #[test]
+    fn package_project_keeps_the_configured_path() {
+        let mut config = Config::from_package("zlib");
+        config.out_dir(env::temp_dir().join("xmakers-test-package"));
+        let path = config.path.clone();
+        assert_eq!(
+            config.get_project_dir(),
+            env::temp_dir()
+                .join("xmakers-test-package")
+                .join("xmakers")
+                .join("package")
+        );
+        config.generate_package_project();
+        assert_eq!(config.path, path);
+        let _ = fs::remove_dir_all(env::temp_dir().join("xmakers-test-package"));
+    }
+
+    #[test]
+    fn jobs_are_limited_by_memory() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        assert_eq!(tune_jobs(8, None), 8);
+        assert_eq!(tune_jobs(8, Some(64 * GIB)), 8);
+        // 1.5 GiB per job
+        assert_eq!(tune_jobs(8, Some(4 * GIB)), 2);
+        assert_eq!(tune_jobs(8, Some(6 * GIB)), 4);
+        // At least one job, even without enough memory or CPUs
+        assert_eq!(tune_jobs(16, Some(GIB / 10)), 1);
+        assert_eq!(tune_jobs(0, None), 1);
+        assert_eq!(tune_jobs(0, Some(64 * GIB)), 1);
+    }
+
+    #[test]
+    fn root_can_be_opted_out() {
+        assert!(!root_allowed_by(Some("0".to_string())));
+        assert!(!root_allowed_by(Some("false".to_string())));
+        assert!(root_allowed_by(Some("1".to_string())));
+        assert!(root_allowed_by(Some("y".to_string())));
+        // Without the variable, only root is allowed
+        assert_eq!(root_allowed_by(None), is_root());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn root_is_allowed_in_the_command() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let xmake_root = ("XMAKE_ROOT".to_string(), Some("y".to_string()));
+        let mut cmd = XmakeCommand::with_program("xmake".into(), Vec::new());
+        if env::var_os("XMAKERS_ALLOW_ROOT").is_none() {
+            assert_eq!(command_envs(&cmd).contains(&xmake_root), is_root());
+        }
+        cmd.allow_root(true);
+        assert!(command_envs(&cmd).contains(&xmake_root));
+        cmd.allow_root(false);
+        assert!(!command_envs(&cmd)
+            .iter()
+            .any(|(key, _)| key == "XMAKE_ROOT"));
+    }
+
+    #[test]
+    fn output_is_printed_with_xmakers_verbose() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = env::var_os("XMAKERS_VERBOSE");
+        let echo = |quiet: bool| {
+            let mut config = Config::new(".");
+            config.out_dir(env::temp_dir()).quiet(quiet);
+            config.xmake_command().echo
+        };
+
+        env::remove_var("XMAKERS_VERBOSE");
+        assert!(!echo(false));
+        env::set_var("XMAKERS_VERBOSE", "1");
+        assert!(echo(false));
+        assert!(!echo(true));
+        env::set_var("XMAKERS_VERBOSE", "0");
+        assert!(!echo(false));
+
+        match previous {
+            Some(value) => env::set_var("XMAKERS_VERBOSE", value),
+            None => env::remove_var("XMAKERS_VERBOSE"),
+        }
+    }
+
+    #[test]
+    fn root_override_reaches_the_command() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = env::var_os("XMAKERS_ALLOW_ROOT");
+        let xmake_root = ("XMAKE_ROOT".to_string(), Some("y".to_string()));
+        let command = || XmakeCommand::with_program("xmake".into(), Vec::new());
+
+        env::set_var("XMAKERS_ALLOW_ROOT", "1");
+        assert!(command_envs(&command()).contains(&xmake_root));
+        env::set_var("XMAKERS_ALLOW_ROOT", "0");
+        assert!(!command_envs(&command())
+            .iter()
+            .any(|(key, _)| key == "XMAKE_ROOT"));
+
+        match previous {
+            Some(value) => env::set_var("XMAKERS_ALLOW_ROOT", value),
+            None => env::remove_var("XMAKERS_ALLOW_ROOT"),
+        }
+    }
+
+    #[test]
+    fn executable_is_searched_in_path_then_locations() {
+        let root = test_dir("search");
+        let (empty, bin, other) = (root.join("empty"), root.join("bin"), root.join("other"));
+        for dir in [&empty, &bin, &other] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let name = if cfg!(windows) { "xmake.exe" } else { "xmake" };
+        fs::write(bin.join(name), "").unwrap();
+        fs::write(other.join(name), "").unwrap();
+        // A directory named like the executable is skipped
+        fs::create_dir_all(empty.join(name)).unwrap();
+
+        let path = env::join_paths([&empty, &bin, &other]).unwrap();
+        assert_eq!(search_executable(Some(path), &[]), Some(bin.join(name)));
+
+        let location = other.join(name);
+        let path = env::join_paths([&empty]).unwrap();
+        assert_eq!(
+            search_executable(Some(path), std::slice::from_ref(&location)),
+            Some(location.clone())
+        );
+        assert_eq!(search_executable(None, &[root.join("missing")]), None);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn version_of_the_configured_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("xmake path").join("bin dir");
+        fs::create_dir_all(&dir).unwrap();
+        let xmake = dir.join("my xmake");
+        fs::write(
+            &xmake,
+            "#!/bin/sh\necho 'xmake v2.9.4+20240829, A cross-platform build utility'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&xmake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = Config::new(&dir);
+        config.xmake_path(&xmake);
+        assert_eq!(config.xmake_version(), Some(&Version::new(2, 9, 4)));
+        // XMAKE names a file, so the space isn't taken as an argument separator
+        assert_eq!(
+            split_program(xmake.as_os_str()),
+            (xmake.clone().into_os_string(), Vec::new())
+        );
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_fails_with_a_too_old_xmake() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("minimum version");
+        fs::create_dir_all(&dir).unwrap();
+        let xmake = dir.join("xmake");
+        fs::write(
+            &xmake,
+            "#!/bin/sh\necho 'xmake v2.9.4+20240829, A cross-platform build utility'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&xmake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = Config::new(&dir);
+        config
+            .xmake_path(&xmake)
+            .out_dir(dir.join("out"))
+            .minimum_version(Version::new(99, 0, 0));
+        let message = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| config.build()))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(
+            message.contains("xmake 2.9.4 was found, but 99.0.0 or newer is required"),
+            "{}",
+            message
+        );
+        let _ = fs::remove_dir_all(dir);
     }
 
-    /// Sets the xmake target for this compilation.
-    /// Note that is different from rust target (os and arch), an xmake target
-    /// can be binary or a library.
-    pub fn target(&mut self, target: &str) -> &mut Config {
-        self.target = Some(target.to_string());
-        self
+    #[test]
+    fn xmake_arch() {
+        let arch = |plat, triple| get_xmake_arch(plat, &Triple::parse(triple));
+        for (plat, triple, expected) in [
+            ("android", "armv7-linux-androideabi", "armeabi-v7a"),
+            ("android", "thumbv7neon-linux-androideabi", "armeabi-v7a"),
+            ("android", "arm-linux-androideabi", "armeabi"),
+            ("android", "aarch64-linux-android", "arm64-v8a"),
+            ("android", "i686-linux-android", "x86"),
+            ("android", "x86_64-linux-android", "x86_64"),
+            ("appletvos", "aarch64-apple-tvos", "arm64"),
+            ("watchos", "arm64_32-apple-watchos", "armv7k"),
+            ("watchos", "armv7k-apple-watchos", "armv7k"),
+            ("iphoneos", "aarch64-apple-ios-sim", "arm64"),
+            ("macosx", "aarch64-apple-darwin", "arm64"),
+            ("macosx", "x86_64-apple-darwin", "x86_64"),
+            ("windows", "i686-pc-windows-msvc", "x86"),
+            ("mingw", "i686-pc-windows-gnu", "i386"),
+            ("wasm", "wasm32-unknown-emscripten", "wasm32"),
+            ("wasm", "wasm64-unknown-unknown", "wasm32"),
+            ("linux", "aarch64-unknown-linux-gnu", "arm64"),
+            ("linux", "i686-unknown-linux-gnu", "i386"),
+            ("linux", "riscv64gc-unknown-linux-gnu", "riscv64"),
+            ("cross", "riscv32imac-unknown-none-elf", "riscv32"),
+            ("linux", "powerpc64le-unknown-linux-gnu", "ppc64le"),
+            ("linux", "powerpc64-unknown-linux-gnu", "ppc64"),
+            ("linux", "powerpc-unknown-linux-gnu", "ppc"),
+            ("linux", "s390x-unknown-linux-gnu", "s390x"),
+            ("linux", "mips64el-unknown-linux-gnuabi64", "mips64el"),
+            ("linux", "mips64-unknown-linux-gnuabi64", "mips64"),
+            ("linux", "mipsel-unknown-linux-gnu", "mipsel"),
+            ("linux", "mips-unknown-linux-gnu", "mips"),
+            ("linux", "loongarch64-unknown-linux-gnu", "loong64"),
+            ("linux", "x86_64-unknown-linux-gnu", "x86_64"),
+            ("cross", "thumbv7em-none-eabihf", "thumbv7em"),
+        ] {
+            assert_eq!(arch(plat, triple), expected, "{} on {}", triple, plat);
+        }
+
+        // powerpc64 is little endian on some targets
+        let mut triple = Triple::parse("powerpc64-unknown-linux-gnu");
+        triple.endian = "little".to_string();
+        assert_eq!(get_xmake_arch("linux", &triple), "ppc64le");
     }
 
-    /// Sets verbose output.
-    pub fn verbose(&mut self, value: bool) -> &mut Config {
-        self.verbose = value;
-        self
+    #[test]
+    fn out_dir_is_cleaned_by_its_owner() {
+        let out_dir = test_dir("clean-on-drop");
+        let mut config = Config::new(&out_dir);
+        config.out_dir(&out_dir).clean_on_drop(true);
+
+        drop(config.clone());
+        assert!(out_dir.exists());
+        drop(config);
+        assert!(!out_dir.exists());
     }
 
-    /// Sets the output directory for this compilation.
-    ///
-    /// This is automatically scraped from `$OUT_DIR` which is set for Cargo
-    /// build scripts so it's not necessary to call this from a build script.
-    pub fn out_dir<P: AsRef<Path>>(&mut self, out: P) -> &mut Config {
-        self.out_dir = Some(out.as_ref().to_path_buf());
-        self
+    #[test]
+    fn unknown_options_are_reported_with_a_suggestion() {
+        let known: Vec<String> = ["mode", "toolchain", "cxflags", "with_ssl", "kind"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            unknown_option_message("toolchian", &known),
+            "unknown xmake option `toolchian`, did you mean `toolchain`?"
+        );
+        assert_eq!(
+            unknown_option_message("with-ssl", &known),
+            "unknown xmake option `with-ssl`, did you mean `with_ssl`?"
+        );
+        assert_eq!(
+            unknown_option_message("use_mode", &known),
+            "unknown xmake option `use_mode`, did you mean `mode`?"
+        );
+        assert_eq!(
+            unknown_option_message("precompiled_headers", &known),
+            "unknown xmake option `precompiled_headers`"
+        );
+
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("mode", "mode"), 0);
     }
 
-    /// Sets the xmake mode for this compilation.
-    pub fn mode(&mut self, mode: &str) -> &mut Config {
-        self.mode = Some(mode.to_string());
-        self
+    /// Splits `s` like `_split_list` of `select_targets.lua`.
+    fn split_escaped(s: &str) -> Vec<String> {
+        let mut items = vec![String::new()];
+        let mut escaped = false;
+        for c in s.chars() {
+            match c {
+                _ if escaped => {
+                    items.last_mut().unwrap().push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                ',' => items.push(String::new()),
+                _ => items.last_mut().unwrap().push(c),
+            }
+        }
+        items
     }
 
-    /// Configure an option for the `xmake` processes spawned by
-    /// this crate in the `build` step.
-    pub fn option<K, V>(&mut self, key: K, value: V) -> &mut Config
-    where
-        K: AsRef<OsStr>,
-        V: AsRef<OsStr>,
-    {
-        self.options
-            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
-        self
+    #[test]
+    fn lists_are_escaped_for_the_lua_scripts() {
+        let items: Vec<String> = ["foo", "a,b", "C:\\dir\\", "x\\,y", "", "sub/dir:bar"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let joined = join_escaped(&items);
+        assert_eq!(joined, "foo,a\\,b,C:\\\\dir\\\\,x\\\\\\,y,,sub/dir:bar");
+        assert_eq!(split_escaped(&joined), items);
+        assert_eq!(join_escaped(&["foo".to_string()]), "foo");
     }
 
-    /// Configure an environment variable for the `xmake` processes spawned by
-    /// this crate in the `build` step.
-    pub fn env<K, V>(&mut self, key: K, value: V) -> &mut Config
-    where
-        K: AsRef<OsStr>,
-        V: AsRef<OsStr>,
-    {
-        self.env
-            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
-        self
+    #[test]
+    fn reduced_debug_information() {
+        let flags = |debug: &str, plat: &str, set: fn(&mut Config)| {
+            let mut config = Config::new(".");
+            set(&mut config);
+            let debug = Some(OsString::from(debug));
+            config
+                .cache
+                .env
+                .borrow_mut()
+                .insert("DEBUG".to_string(), debug);
+            config.compiler_flags(plat)
+        };
+        assert_eq!(flags("1", "linux", |_| {}), ["-g1"]);
+        assert_eq!(flags("limited", "macosx", |_| {}), ["-g1"]);
+        assert_eq!(flags("line-tables-only", "mingw", |_| {}), ["-g1"]);
+        assert!(flags("true", "linux", |_| {}).is_empty());
+        assert!(flags("2", "linux", |_| {}).is_empty());
+        assert!(flags("0", "linux", |_| {}).is_empty());
+        // MSVC and emscripten don't know -g1
+        assert!(flags("1", "windows", |_| {}).is_empty());
+        assert!(flags("1", "wasm", |_| {}).is_empty());
+        // The mode and the flags given by the user win
+        assert!(flags("1", "linux", |c| {
+            c.mode("debug");
+        })
+        .is_empty());
+        assert!(flags("1", "linux", |c| {
+            c.option("cxflags", "-O2");
+        })
+        .is_empty());
     }
 
-    /// Configures runtime type (static or not)
-    ///
-    /// This option defaults to `false`.
-    pub fn static_crt(&mut self, static_crt: bool) -> &mut Config {
-        self.static_crt = Some(static_crt);
-        self
+    #[test]
+    fn project_is_locked_by_one_build_at_a_time() {
+        use std::sync::atomic::AtomicUsize;
+
+        let project = test_dir("lock");
+        let inside = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let (project, inside) = (project.clone(), inside.clone());
+                thread::spawn(move || {
+                    let lock = Config::new(&project).lock_project();
+                    assert!(lock.is_some());
+                    assert_eq!(inside.fetch_add(1, Ordering::SeqCst), 0);
+                    thread::sleep(Duration::from_millis(50));
+                    inside.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let _ = fs::remove_dir_all(project);
     }
 
-    /// Set the standard library to link against when compiling with C++
-    /// support (only Android).
-    /// The given library name must not contain the `lib` prefix.
-    ///
-    ///
-    /// Common values:
-    /// - `c++_static`
-    /// - `c++_shared`
-    /// - `gnustl_static`
-    /// - `gnustl_shared`
-    /// - `stlport_shared`
-    /// - `stlport_static`
-    pub fn cpp_link_stdlib(&mut self, stblib: &str) -> &mut Config {
-        self.cpp_link_stdlib = Some(stblib.to_string());
-        self
+    #[test]
+    fn project_is_watched_with_the_first_rerun_directive() {
+        let project = Path::new("/tmp/watch");
+        let config = Config::new(project);
+        assert_eq!(
+            config.directive_lines("rustc-link-lib=foo"),
+            ["rustc-link-lib=foo"]
+        );
+        assert_eq!(
+            config.directive_lines("rerun-if-env-changed=CC"),
+            [
+                format!("rerun-if-changed={}", project.display()),
+                "rerun-if-env-changed=CC".to_string(),
+            ]
+        );
+        assert_eq!(
+            config.directive_lines("rerun-if-env-changed=CXX"),
+            ["rerun-if-env-changed=CXX"]
+        );
+
+        // A generated package project isn't watched
+        let config = Config::from_package("zlib");
+        assert_eq!(
+            config.directive_lines("rerun-if-env-changed=CC"),
+            ["rerun-if-env-changed=CC"]
+        );
     }
 
-    /// Run this configuration, compiling the library with all the configured
-    /// options.
-    ///
-    /// This will run both the configuration command as well as the
-    /// command to build the library.
-    pub fn build(&mut self) -> PathBuf {
-        self.config();
+    #[test]
+    fn configuration_is_kept_in_the_build_dir() {
+        let mut config = Config::new(".");
+        config.out_dir("/tmp/out").build_dir("/tmp/build");
+        let configdir = (
+            "XMAKE_CONFIGDIR".to_string(),
+            Some(Path::new("/tmp/build").display().to_string()),
+        );
+        assert!(command_envs(&config.xmake_command()).contains(&configdir));
+    }
 
-        let mut cmd = self.xmake_command();
-        cmd.arg("build");
+    #[test]
+    fn position_independent_code_is_opt_in() {
+        let mut config = Config::new(".");
+        let debug = Some(OsString::from("false"));
+        config
+            .cache
+            .env
+            .borrow_mut()
+            .insert("DEBUG".to_string(), debug);
+        assert!(config.compiler_flags("linux").is_empty());
+        assert!(config.compiler_flags("cross").is_empty());
+        config.pic(true);
+        assert_eq!(config.compiler_flags("linux"), ["-fPIC"]);
+        assert!(config.compiler_flags("windows").is_empty());
+    }
 
-        // In case of xmake is waiting to download something
-        cmd.arg("--yes");
-        if self.verbose {
-            cmd.arg("-v");
-        }
+    #[test]
+    fn target_addresses() {
+        assert_eq!(split_single_colon("sub/dir:foo"), Some(("sub/dir", "foo")));
+        assert_eq!(
+            split_single_colon("sub/dir:ns::foo"),
+            Some(("sub/dir", "ns::foo"))
+        );
+        assert_eq!(split_single_colon("ns::foo"), None);
+        assert_eq!(split_single_colon("foo"), None);
+        // Only the first colon separates the key from the value
+        assert_eq!(
+            split_single_colon("linkdirs:C:/lib"),
+            Some(("linkdirs", "C:/lib"))
+        );
 
-        if self.target.is_some() {
-            cmd.arg(self.target.clone().unwrap());
-        }
+        assert_eq!(target_name("sub/dir:foo"), "foo");
+        assert_eq!(target_name("sub/dir:ns::foo"), "ns::foo");
+        assert_eq!(target_name("ns::foo"), "ns::foo");
+        assert_eq!(target_name("foo"), "foo");
+    }
 
-        run(&mut cmd, "xmake");
+    #[test]
+    fn links_and_link_directories_are_deduplicated() {
+        let info =
+            parse_info("linkdirs:/a|/b|/a\nlinks:foo/static|bar/shared|foo/static|foo/shared");
+        assert_eq!(info.linkdirs(), [PathBuf::from("/a"), PathBuf::from("/b")]);
+        let links: Vec<_> = info.links().iter().map(|l| (l.name(), l.kind())).collect();
+        assert_eq!(
+            links,
+            [
+                ("foo", LinkKind::Static),
+                ("bar", LinkKind::Dynamic),
+                ("foo", LinkKind::Dynamic),
+            ]
+        );
+    }
 
-        // XMake put libary in the lib folder
-        let dst = self.install().join("lib");
-        println!("cargo:root={}", dst.display());
+    #[test]
+    fn repeated_keys_are_merged() {
+        let info = parse_info(
+            "includedirs_target.foo:/a\nlinks:foo/static\nincludedirs_target.foo:/b|/a\nlinks:bar/static",
+        );
+        let dirs: Vec<_> = info.includedirs_iter(Source::Target, "foo").collect();
+        assert_eq!(dirs, [Path::new("/a"), Path::new("/b")]);
+        let names: Vec<_> = info.links().iter().map(Link::name).collect();
+        assert_eq!(names, ["foo", "bar"]);
+    }
 
-        dst
+    #[test]
+    fn include_directories_are_sorted_and_deduplicated() {
+        let info = parse_info(
+            "includedirs_target.zlib:/z|/shared\nincludedirs_target.bar:/b|/shared\nincludedirs_package.fmt:/fmt|/b",
+        );
+        assert_eq!(info.target_names().collect::<Vec<_>>(), ["bar", "zlib"]);
+        assert_eq!(info.package_names().collect::<Vec<_>>(), ["fmt"]);
+        let paths = |dirs: &[&str]| dirs.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            info.all_includedirs(),
+            paths(&["/b", "/shared", "/z", "/fmt"])
+        );
+        assert_eq!(
+            info.includedirs(Source::Target, "*"),
+            paths(&["/b", "/shared", "/z"])
+        );
+        assert_eq!(
+            info.includedirs(Source::Package, "*"),
+            paths(&["/fmt", "/b"])
+        );
+        assert_eq!(
+            info.clang_include_args(),
+            ["-I/b", "-I/shared", "-I/z", "-I/fmt"]
+        );
     }
 
-    // Run the configuration with all the configured
-    /// options.
-    fn config(&mut self) {
-        let mut cmd = self.xmake_command();
-        cmd.arg("config");
+    #[test]
+    fn options_are_replaced_and_removed() {
+        let mut config = Config::new(".");
+        config
+            .option("with_ssl", true)
+            .option("level", 1)
+            .option("with_ssl", false);
+        assert_eq!(config.option_args(&[]), ["--with_ssl=n", "--level=1"]);
+        config.remove_option("with_ssl").remove_option("unset");
+        assert_eq!(config.option_args(&[]), ["--level=1"]);
+        config.option("with_ssl", true).clear_options();
+        assert!(config.option_args(&[]).is_empty());
+    }
 
-        // In case of xmake is waiting to download something
-        cmd.arg("--yes");
+    #[test]
+    fn link_directives_without_auto_link() {
+        let libdir = test_dir("link-directives");
+        fs::write(libdir.join("libbaz.so.1"), "").unwrap();
+        fs::write(libdir.join("libfoo.so"), "").unwrap();
+        let info = BuildInfo {
+            linkdirs: vec![PathBuf::from("/deps"), libdir.clone()],
+            frameworkdirs: vec![PathBuf::from("/frameworks")],
+            links: vec![
+                Link::new("foo", LinkKind::Static),
+                Link::new("bar", LinkKind::Dynamic),
+                Link::new("foo", LinkKind::Dynamic),
+            ],
+            syslinks: vec!["pthread".to_string(), "z".to_string()],
+            use_stl: true,
+            libdir: Some(libdir.clone()),
+            ..BuildInfo::default()
+        };
 
-        let dst = self
-            .out_dir
-            .clone()
-            .unwrap_or_else(|| PathBuf::from(getenv_unwrap("OUT_DIR")));
+        // What a build script calling the method after a build with
+        // auto_link(false) prints, the same directives as Config::build
+        let mut options = LinkOptions::new("linux");
+        options
+            .search_path("/extra")
+            .whole_archive("foo")
+            .rpath(RpathMode::Origin)
+            .link_arg("-Wl,--as-needed");
+        assert_eq!(
+            info.link_directives(&options),
+            [
+                format!("rustc-link-search=all={}", libdir.display()),
+                "rustc-link-search=all=/deps".to_string(),
+                "rustc-link-search=all=/extra".to_string(),
+                "warning=the library `foo` is linked both as static and shared".to_string(),
+                "rustc-link-lib=static:+whole-archive,-bundle=foo".to_string(),
+                "rustc-link-lib=dylib=bar".to_string(),
+                "rustc-link-lib=dylib=foo".to_string(),
+                "rustc-link-lib=stdc++".to_string(),
+                "rustc-link-lib=pthread".to_string(),
+                "rustc-link-lib=dl".to_string(),
+                "rustc-link-lib=m".to_string(),
+                "rustc-link-lib=z".to_string(),
+                "rustc-link-lib=dylib=baz".to_string(),
+                "rustc-link-arg=-Wl,-rpath,$ORIGIN".to_string(),
+                "rustc-link-arg=-Wl,--as-needed".to_string(),
+            ]
+        );
 
-        cmd.arg(format!("--buildir={}", dst.display()));
+        // The framework directories are only searched on Apple platforms
+        let options = LinkOptions::new("macosx");
+        let directives = info.link_directives(&options);
+        assert!(directives.contains(&"rustc-link-search=framework=/frameworks".to_string()));
+        assert!(directives.contains(&"rustc-link-lib=c++".to_string()));
+        let _ = fs::remove_dir_all(libdir);
+    }
 
-        if self.verbose {
-            cmd.arg("-v");
+    #[test]
+    fn ndk_library_directories() {
+        let ndk = test_dir("ndk");
+        let libdir = ndk.join("toolchains/llvm/prebuilt/linux-x86_64/sysroot/usr/lib");
+        for dir in ["aarch64-linux-android/21", "arm-linux-androideabi"] {
+            fs::create_dir_all(libdir.join(dir)).unwrap();
         }
 
-        // Cross compilation
-        let host = getenv_unwrap("HOST");
-        let target = getenv_unwrap("TARGET");
+        let aarch64 = libdir.join("aarch64-linux-android");
+        assert_eq!(
+            get_ndk_libdirs(&ndk, "aarch64-linux-android", Some("android-21")),
+            [aarch64.join("21"), aarch64.clone()]
+        );
+        assert_eq!(
+            get_ndk_libdirs(&ndk, "aarch64-linux-android", Some("21")),
+            [aarch64.join("21"), aarch64.clone()]
+        );
+        // The directory of a missing API level is skipped
+        assert_eq!(
+            get_ndk_libdirs(&ndk, "aarch64-linux-android", Some("30")),
+            [aarch64]
+        );
+        assert_eq!(
+            get_ndk_libdirs(&ndk, "armv7-linux-androideabi", None),
+            [libdir.join("arm-linux-androideabi")]
+        );
+        assert!(get_ndk_libdirs(&ndk, "x86_64-linux-android", None).is_empty());
+        assert!(get_ndk_libdirs(&ndk, "riscv64-linux-android", None).is_empty());
+        assert!(get_ndk_libdirs(&ndk.join("missing"), "aarch64-linux-android", None).is_empty());
+        let _ = fs::remove_dir_all(ndk);
+    }
 
-        // List of xmake platform https://github.com/xmake-io/xmake/tree/master/xmake/platforms
-        let os = getenv_unwrap("CARGO_CFG_TARGET_OS");
-        let plat = match self.get_xmake_plat(os.clone()) {
-            Some(p) => p,
-            None => panic!("unsupported rust target: {}", os),
+    #[test]
+    fn shared_library_names() {
+        assert_eq!(shared_lib_name("libfoo.so"), Some("foo"));
+        assert_eq!(shared_lib_name("libfoo.so.1"), Some("foo"));
+        assert_eq!(shared_lib_name("libfoo.so.1.2.3"), Some("foo"));
+        assert_eq!(shared_lib_name("libfoo-bar.so.12"), Some("foo-bar"));
+        assert_eq!(shared_lib_name("libfoo.so."), None);
+        assert_eq!(shared_lib_name("libfoo.so.1a"), None);
+        assert_eq!(shared_lib_name("libfoo.so.debug"), None);
+        assert_eq!(shared_lib_name("libfoo.a"), None);
+        assert_eq!(shared_lib_name("foo.so"), None);
+        assert_eq!(shared_lib_name("lib.so"), None);
+    }
+
+    #[test]
+    fn triples() {
+        let triple = |arch: &str, os: &str, env: &str, family: &str, endian: &str| Triple {
+            arch: arch.to_string(),
+            os: os.to_string(),
+            env: env.to_string(),
+            family: family.to_string(),
+            endian: endian.to_string(),
         };
+        for (target, expected, plat) in [
+            (
+                "aarch64-apple-ios-sim",
+                triple("aarch64", "ios", "", "unix", "little"),
+                "iphoneos",
+            ),
+            (
+                "armv7-linux-androideabi",
+                triple("armv7", "android", "", "unix", "little"),
+                "android",
+            ),
+            (
+                "x86_64-pc-windows-gnu",
+                triple("x86_64", "windows", "gnu", "windows", "little"),
+                "mingw",
+            ),
+            (
+                "wasm32-unknown-emscripten",
+                triple("wasm32", "emscripten", "", "unix", "little"),
+                "wasm",
+            ),
+            (
+                "riscv64gc-unknown-linux-gnu",
+                triple("riscv64gc", "linux", "gnu", "unix", "little"),
+                "linux",
+            ),
+        ] {
+            let parsed = Triple::parse(target);
+            assert_eq!(parsed, expected, "{}", target);
+            assert_eq!(get_xmake_plat(&parsed).as_deref(), Some(plat), "{}", target);
+        }
+        assert_eq!(
+            Triple::parse("wasm32-unknown-unknown"),
+            triple("wasm32", "none", "", "wasm", "little")
+        );
+        assert_eq!(
+            Triple::parse("thumbv7em-none-eabihf"),
+            triple("thumbv7em", "none", "", "", "little")
+        );
 
-        if host != target {
-            let arch = match (
-                plat.as_str(),
-                getenv_unwrap("CARGO_CFG_TARGET_ARCH").as_str(),
-            ) {
-                ("android", a) if os == "androideabi" => match a {
-                    "arm" => "armeabi", // TODO Check with cc-rs if it's true
-                    "armv7" => "armeabi-v7a",
-                    a => a,
-                },
-                ("android", "aarch64") => "arm64-v8a",
-                ("android", "i686") => "x86",
-                ("appletvos", "aarch64") => "arm64",
-                ("watchos", "arm64_32") => "armv7k",
-                ("watchos", "armv7k") => "armv7k",
-                ("iphoneos", "aarch64") => "arm64",
-                ("macosx", "aarch64") => "arm64",
-                ("windows", "i686") => "x86",
-                ("wasm", _) => "wasm32",
-                (_, "aarch64") => "arm64",
-                (_, "i686") => "i386",
-                (_, a) => a,
-            }
-            .to_string();
-
-            cmd.arg(format!("--plat={}", plat));
-            if plat != "cross" {
-                //cmd.arg(format!("--arch={}", arch));
+        // The variables of cargo win over the triple
+        let cfg = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                let value = vars.iter().find(|(key, _)| *key == name);
+                value.map(|(_, value)| value.to_string())
             }
+        };
+        let emscripten = Triple::from_cfg(
+            "wasm32-unknown-emscripten",
+            cfg(&[
+                ("CARGO_CFG_TARGET_OS", "emscripten"),
+                ("CARGO_CFG_TARGET_FAMILY", "unix,wasm"),
+                ("CARGO_CFG_TARGET_ENDIAN", "little"),
+            ]),
+        );
+        assert_eq!(
+            emscripten,
+            triple("wasm32", "emscripten", "", "unix", "little")
+        );
+        let simulator = Triple::from_cfg(
+            "aarch64-apple-ios-sim",
+            cfg(&[
+                ("CARGO_CFG_TARGET_OS", "ios"),
+                ("CARGO_CFG_TARGET_ENV", "sim"),
+                ("CARGO_CFG_TARGET_FAMILY", "unix"),
+            ]),
+        );
+        assert_eq!(simulator, triple("aarch64", "ios", "sim", "unix", "little"));
+        let custom = Triple::from_cfg(
+            "mips64-custom-elf",
+            cfg(&[
+                ("CARGO_CFG_TARGET_OS", "none"),
+                ("CARGO_CFG_TARGET_ENDIAN", "little"),
+            ]),
+        );
+        assert_eq!(custom, triple("mips64", "none", "", "", "little"));
+        assert_eq!(get_xmake_plat(&custom).as_deref(), Some("cross"));
+        assert_eq!(get_xmake_arch("cross", &custom), "mips64el");
+    }
 
-            if plat == "android" {
-                if let Ok(ndk) = env::var("ANDROID_NDK_HOME") {
-                    cmd.arg(format!("--ndk={}", ndk));
-                }
-                if self.cpp_link_stdlib.is_some() {
-                    cmd.arg(format!(
-                        "--ndk_cxxstl={}",
-                        self.cpp_link_stdlib.clone().unwrap()
-                    ));
-                }
-                cmd.arg(format!("--toolchain={}", "ndk"));
-            }
+    #[test]
+    fn rebuilds_are_detected_from_the_output() {
+        let first = "checking for platform ... linux
+[ 25%]: cache compiling.release src/foo.c
+[ 50%]: compiling.release src/bar.cpp
+[ 75%]: archiving.release libfoo.a
+[100%]: build ok, spent 1.2s";
+        assert!(did_build(first));
+        // A second identical build has nothing to do
+        assert!(!did_build("[100%]: build ok, spent 0.05s"));
+        assert!(!did_build(""));
+        assert!(did_build("[ 90%]: linking.release libfoo.so"));
+        // The steps are only read from the progress lines
+        assert!(!did_build("compiling src/foo.c"));
 
-            if plat == "wasm" {
-                if let Ok(emscripten) = env::var("EMSCRIPTEN_HOME") {
-                    cmd.arg(format!("--emsdk={}", emscripten));
-                }
-                cmd.arg(format!("--toolchain={}", "emcc"));
-            }
+        let config = Config::new(".");
+        assert!(!config.was_rebuilt());
+    }
 
-            if plat == "cross" {
-                let mut c_cfg = cc::Build::new();
-                c_cfg
-                    .cargo_metadata(false)
-                    .opt_level(0)
-                    .debug(false)
-                    .warnings(false)
-                    .host(&host)
-                    .target(&target);
+    #[test]
+    fn unchanged_configuration_is_skipped() {
+        let command = |args: &[&str]| {
+            let mut cmd = XmakeCommand::with_program("xmake".into(), Vec::new());
+            cmd.task("config").args(args).env("CC", "clang");
+            cmd
+        };
+        let version = Some(Version::new(2, 9, 4));
+        let first = fingerprint(&command(&["-m", "release"]), version);
+        assert_eq!(first, fingerprint(&command(&["-m", "release"]), version));
+        assert_ne!(first, fingerprint(&command(&["-m", "debug"]), version));
+        assert_ne!(
+            first,
+            fingerprint(&command(&["-m", "release", "--with_ssl=y"]), version)
+        );
+        let mut cmd = command(&["-m", "release"]);
+        cmd.env("CXX", "clang++");
+        assert_ne!(first, fingerprint(&cmd, version));
+        assert_ne!(
+            first,
+            fingerprint(&command(&["-m", "release"]), Some(Version::new(2, 9, 5)))
+        );
 
-                // Attempt to find the cross compilation sdk
-                // Let cc find it for us
-                // Usually a compiler is inside bin folder and xmake wait the entire
-                // sdk folder
-                let compiler = c_cfg.get_compiler();
-                let sdk = compiler.path().ancestors().nth(2).unwrap();
+        let project = test_dir("fingerprint");
+        let build_dir = project.join("out").join("build");
+        let fingerprint_file = project.join("out").join("config.fingerprint");
+        let mut config = Config::new(&project);
+        // Nothing was configured yet
+        assert!(!config.configuration_unchanged(&first, &fingerprint_file, &build_dir));
 
-                cmd.arg(format!("--sdk={}", sdk.display()));
-                cmd.arg(format!("--cross={}-{}", arch, os));
-                cmd.arg(format!("--toolchain={}", "cross"));
-            }
-        } else {
-            cmd.arg(format!("--plat={}", plat));
-        }
+        let conf_dir = build_dir.join(".xmake").join("linux").join("x86_64");
+        fs::create_dir_all(&conf_dir).unwrap();
+        let conf = format!("{{ buildir = \"{}\" }}", build_dir.display());
+        fs::write(conf_dir.join("xmake.conf"), conf).unwrap();
+        fs::write(&fingerprint_file, &first).unwrap();
+        assert!(config.configuration_unchanged(&first, &fingerprint_file, &build_dir));
+        let other = fingerprint(&command(&["-m", "debug"]), version);
+        assert!(!config.configuration_unchanged(&other, &fingerprint_file, &build_dir));
+        // The project was configured for another build directory since
+        let elsewhere = project.join("elsewhere");
+        assert!(!config.configuration_unchanged(&first, &fingerprint_file, &elsewhere));
+        config.always_configure(true);
+        assert!(!config.configuration_unchanged(&first, &fingerprint_file, &build_dir));
+        let _ = fs::remove_dir_all(project);
+    }
 
-        if plat == "windows" {
-            // Static CRT
-            let static_crt = self.static_crt.unwrap_or_else(|| self.get_static_crt());
-            let debug = match self.get_mode() {
-                // rusct doesn't support debug version of the CRT
-                // "debug" => "d",
-                // "releasedbg" => "d",
-                _ => "",
-            };
+    #[cfg(feature = "log")]
+    #[test]
+    fn diagnostics_are_logged() {
+        struct Records(Mutex<Vec<(log::Level, String)>>);
 
-            let runtime = match static_crt {
-                true => format!("--runtimes=MT{}", debug),
-                false => format!("--runtimes=MD{}", debug),
-            };
+        impl log::Log for Records {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
 
-            cmd.arg(runtime);
+            fn log(&self, record: &log::Record) {
+                let line = record.args().to_string();
+                self.0.lock().unwrap().push((record.level(), line));
+            }
+
+            fn flush(&self) {}
         }
 
-        // Compilation mode: release, debug...
-        let mode = self.get_mode();
-        cmd.arg("-m").arg(mode);
+        static RECORDS: Records = Records(Mutex::new(Vec::new()));
+        log::set_logger(&RECORDS).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
 
-        // Option
-        for (key, val) in self.options.iter() {
-            let option = format!(
-                "--{}={}",
-                key.clone().into_string().unwrap(),
-                val.clone().into_string().unwrap()
-            );
-            cmd.arg(option);
-        }
+        debug_line(false, "running: \"xmake\" \"build\"");
+        trace_line(false, "CC = Some(\"clang\")");
+        let records = RECORDS.0.lock().unwrap();
+        assert!(records.contains(&(
+            log::Level::Debug,
+            "running: \"xmake\" \"build\"".to_string()
+        )));
+        assert!(records.contains(&(log::Level::Trace, "CC = Some(\"clang\")".to_string())));
+    }
 
-        run(&mut cmd, "xmake");
+    #[test]
+    fn progress_is_parsed() {
+        assert_eq!(
+            parse_progress("[ 42%]: compiling.release src/foo.c"),
+            Some(42)
+        );
+        assert_eq!(parse_progress("[100%]: build ok, spent 1.2s"), Some(100));
+        assert_eq!(
+            parse_progress("[  5%]: cache compiling.release a.c"),
+            Some(5)
+        );
+        assert_eq!(parse_progress("checking for platform ... linux"), None);
+        assert_eq!(parse_progress(" [ 42%]: indented"), None);
+        assert_eq!(parse_progress("[abc%]: compiling"), None);
+
+        let minutes = Duration::from_secs(150);
+        assert_eq!(
+            heartbeat_message(Some(7), minutes),
+            "xmake: still building [  7%] (2m elapsed)"
+        );
+        assert_eq!(
+            heartbeat_message(None, Duration::from_secs(12)),
+            "xmake: still building (12s elapsed)"
+        );
     }
 
-    /// Install target in OUT_DIR.
-    fn install(&mut self) -> PathBuf {
-        let mut cmd = self.xmake_command();
-        cmd.arg("install");
+    #[cfg(unix)]
+    #[test]
+    fn slow_commands_report_heartbeats() {
+        use std::os::unix::fs::PermissionsExt;
 
-        let dst = self
-            .out_dir
-            .clone()
-            .unwrap_or_else(|| PathBuf::from(getenv_unwrap("OUT_DIR")));
+        static REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-        cmd.arg("-o").arg(dst.clone());
-        if self.verbose {
-            cmd.arg("-v");
-        }
+        let dir = test_dir("heartbeat");
+        let xmake = dir.join("xmake");
+        fs::write(
+            &xmake,
+            "#!/bin/sh\necho '[ 42%]: compiling.release foo.c'\nsleep 1\n",
+        )
+        .unwrap();
+        fs::set_permissions(&xmake, fs::Permissions::from_mode(0o755)).unwrap();
 
-        if self.target.is_some() {
-            cmd.arg(self.target.clone().unwrap());
+        let mut cmd = XmakeCommand::with_program(xmake.into_os_string(), Vec::new());
+        cmd.echo = false;
+        cmd.progress_interval = Some(Duration::from_millis(200));
+        cmd.report = |message| REPORTS.lock().unwrap().push(message.to_string());
+        cmd.task("build").run().unwrap();
+
+        let reports = REPORTS.lock().unwrap();
+        assert!(!reports.is_empty());
+        assert!(reports
+            .iter()
+            .all(|r| r.starts_with("xmake: still building [ 42%]")));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn planned_artifacts_are_installed() {
+        let dst = test_dir("artifacts");
+        let linux =
+            "__xmakers_start__\nartifacts:lib/libfoo.a|lib/libbar.so|bin/tool\n__xmakers_end__";
+        let planned = artifact_paths(linux, &dst, "lib64");
+        assert_eq!(
+            planned,
+            [
+                dst.join("lib64").join("libfoo.a"),
+                dst.join("lib64").join("libbar.so"),
+                dst.join("bin").join("tool"),
+            ]
+        );
+        let shared: Vec<_> = planned
+            .iter()
+            .filter_map(|path| shared_lib_name(path.file_name()?.to_str()?))
+            .collect();
+        assert_eq!(shared, ["bar"]);
+
+        // What an install of the same targets produces
+        for path in planned.iter() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
         }
+        assert!(planned.iter().all(|path| path.is_file()));
 
-        run(&mut cmd, "xmake");
-        dst
+        // Shared libraries go to bin on Windows, with their import library
+        let windows = "artifacts:bin/bar.dll|lib/bar.lib|lib/foo.lib|bin/tool.exe";
+        assert_eq!(
+            artifact_paths(windows, &dst, "lib"),
+            [
+                dst.join("bin").join("bar.dll"),
+                dst.join("lib").join("bar.lib"),
+                dst.join("lib").join("foo.lib"),
+                dst.join("bin").join("tool.exe"),
+            ]
+        );
+        assert!(artifact_paths("artifacts:", &dst, "lib").is_empty());
+        let _ = fs::remove_dir_all(dst);
     }
 
-    fn get_static_crt(&self) -> bool {
-        let feature = env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or(String::new());
-        if feature.contains("crt-static") {
-            true
-        } else {
-            false
+    #[cfg(unix)]
+    #[test]
+    fn session_runs_xmake_once() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("session");
+        let xmake = dir.join("xmake");
+        let calls = dir.join("calls");
+        let script = format!(
+            "#!/bin/sh\necho \"$@\" >> '{}'\necho 'xmake v2.9.4+20240829, A cross-platform build utility'\n",
+            calls.display()
+        );
+        fs::write(&xmake, script).unwrap();
+        fs::set_permissions(&xmake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let session = {
+            let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            env::set_var("XMAKE", &xmake);
+            let session = XmakeSession::new();
+            env::remove_var("XMAKE");
+            session
+        };
+        let mut foo = session.config(dir.join("foo"));
+        let mut bar = session.config(dir.join("bar"));
+        assert_eq!(foo.xmake_version(), Some(&Version::new(2, 9, 4)));
+        assert_eq!(bar.xmake_version(), Some(&Version::new(2, 9, 4)));
+        assert_eq!(fs::read_to_string(&calls).unwrap(), "--version\n");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn split_debug_symbols_are_found() {
+        let dir = test_dir("debug-symbols");
+        for sub in ["obj/src", "bin/foo.dSYM/Contents", "lib"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
         }
+        for file in [
+            "obj/src/foo.dwo",
+            "obj/src/foo.o",
+            "bin/foo.dSYM/Contents/Info.plist",
+            "bin/foo",
+            "lib/foo.dwp",
+            "lib/foo.pdb",
+            "lib/libfoo.a",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let mut paths = Vec::new();
+        find_debug_symbols(&dir, &mut paths);
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                dir.join("bin/foo.dSYM"),
+                dir.join("lib/foo.dwp"),
+                dir.join("lib/foo.pdb"),
+                dir.join("obj/src/foo.dwo"),
+            ]
+        );
+        let _ = fs::remove_dir_all(dir);
     }
 
-    /// Convert rust platform to xmake one
-    fn get_xmake_plat(&self, platform: String) -> Option<String> {
-        // List of xmake platform https://github.com/xmake-io/xmake/tree/master/xmake/platforms
-        match platform.as_str() {
-            "windows" => Some("windows".to_string()),
-            "linux" => Some("linux".to_string()),
-            "android" => Some("android".to_string()),
-            "androideabi" => Some("android".to_string()),
-            "emscripten" => Some("wasm".to_string()),
-            "macos" => Some("macosx".to_string()),
-            "ios" => Some("iphoneos".to_string()),
-            "tvos" => Some("appletvos".to_string()),
-            "fuchsia" => None,
-            "solaris" => None,
-            _ if getenv_unwrap("CARGO_CFG_TARGET_FAMILY") == "wasm" => Some("wasm".to_string()),
-            _ => Some("cross".to_string()),
+    #[test]
+    fn installed_debug_symbols_are_listed_first() {
+        let dir = test_dir("debug-install");
+        let (out, build) = (dir.join("out"), dir.join("build"));
+        let lib = out.join("lib");
+        for sub in [&lib, &build.join("obj"), &out.join("bin")] {
+            fs::create_dir_all(sub).unwrap();
         }
+        let (dwo, pdb) = (build.join("obj/foo.dwo"), out.join("bin/foo.pdb"));
+        fs::write(&dwo, "").unwrap();
+        fs::write(&pdb, "").unwrap();
+
+        let mut config = Config::new(".");
+        config.out_dir(&out).build_dir(&build);
+        // Only the symbols of the build directory are installed
+        let installed = config.install_debug_files(&[pdb.clone(), dwo.clone()], &lib);
+        assert_eq!(installed, [lib.join("foo.dwo")]);
+        assert!(lib.join("foo.dwo").exists());
+
+        let mut info = parse_info("");
+        info.debug_symbols = vec![lib.join("foo.dwo"), pdb.clone(), dwo.clone()];
+        info.installed_debug_symbols = 1;
+        assert_eq!(info.debug_files(), [lib.join("foo.dwo")]);
+        assert_eq!(info.debug_symbol_paths(), [lib.join("foo.dwo"), pdb, dwo]);
+        let _ = fs::remove_dir_all(dir);
     }
 
-    /// Return xmake mode or inferred from Rust's compilation profile.
-    ///
-    /// * if `opt-level=0` then `debug`,
-    /// * if `opt-level={1,2,3}` and:
-    ///   * `debug=false` then `release`
-    ///   * otherwise `releasedbg`
-    /// * if `opt-level={s,z}` then `minsizerel`
-    fn get_mode(&self) -> &str {
-        if let Some(profile) = self.mode.as_ref() {
-            profile
-        } else {
-            #[derive(PartialEq)]
-            enum RustProfile {
-                Debug,
-                Release,
-            }
-            #[derive(PartialEq, Debug)]
-            enum OptLevel {
-                Debug,
-                Release,
-                Size,
-            }
+    /// Returns build information with every field set, with Windows paths
+    /// holding spaces and non-ASCII characters.
+    fn full_build_info() -> BuildInfo {
+        let mut info = parse_info(concat!(
+            "linkdirs:C:\\Program Files (x86)\\Bibliothèque\\lib|/usr/lib\n",
+            "frameworkdirs:/Library/Frameworks\n",
+            "links:foo/static|crypto/shared|m/static\n",
+            "links_target.foo:foo/static\n",
+            "links_package.openssl:crypto/shared\n",
+            "syslinks:pthread|ws2_32\n",
+            "packages:openssl/3.0.0|zlib/\n",
+            "includedirs_target.foo:C:\\Users\\Zoë\\foo include\n",
+            "includedirs_package.openssl:/openssl/include|/openssl/include/ssl",
+        ));
+        info.use_cxx = true;
+        info.compile_commands = Some(PathBuf::from("/build/compile_commands.json"));
+        info.debug_symbols = vec![PathBuf::from("/out/lib/foo.dwo"), "/build/foo.pdb".into()];
+        info.installed_debug_symbols = 1;
+        info.libdir = Some(PathBuf::from("C:\\Program Files\\out\\lib"));
+        info
+    }
 
-            let rust_profile = match &getenv_unwrap("PROFILE")[..] {
-                "debug" => RustProfile::Debug,
-                "release" | "bench" => RustProfile::Release,
-                unknown => {
-                    eprintln!(
-                        "Warning: unknown Rust profile={}; defaulting to a release build.",
-                        unknown
-                    );
-                    RustProfile::Release
-                }
-            };
+    #[test]
+    fn build_info_round_trips_through_its_lines() {
+        let info = full_build_info();
+        assert_eq!(info.to_string().parse::<BuildInfo>(), Ok(info));
+        let empty = BuildInfo::default();
+        assert_eq!(empty.to_string().parse::<BuildInfo>(), Ok(empty));
+    }
 
-            let opt_level = match &getenv_unwrap("OPT_LEVEL")[..] {
-                "0" => OptLevel::Debug,
-                "1" | "2" | "3" => OptLevel::Release,
-                "s" | "z" => OptLevel::Size,
-                unknown => {
-                    let default_opt_level = match rust_profile {
-                        RustProfile::Debug => OptLevel::Debug,
-                        RustProfile::Release => OptLevel::Release,
-                    };
-                    eprintln!(
-                        "Warning: unknown opt-level={}; defaulting to a {:?} build.",
-                        unknown, default_opt_level
-                    );
-                    default_opt_level
-                }
-            };
+    #[test]
+    fn build_info_is_saved_and_loaded() {
+        let dir = test_dir("build-info-save");
+        let file = dir.join("build_info.txt");
+        let info = full_build_info();
+        info.save(&file).unwrap();
+        assert_eq!(BuildInfo::load(&file).unwrap(), info);
 
-            let debug_info: bool = match &getenv_unwrap("DEBUG")[..] {
-                "false" => false,
-                "true" => true,
-                unknown => {
-                    eprintln!("Warning: unknown debug={}; defaulting to `true`.", unknown);
-                    true
-                }
-            };
+        // A separator in a path can't be written as key:value lines
+        let mut invalid = BuildInfo::default();
+        invalid.linkdirs.push(PathBuf::from("/a|b"));
+        let err = invalid.save(dir.join("invalid.txt")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(!dir.join("invalid.txt").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
 
-            match (opt_level, debug_info) {
-                (OptLevel::Debug, _) => "debug",
-                (OptLevel::Release, false) => "release",
-                (OptLevel::Release, true) => "releasedbg",
-                (OptLevel::Size, _) => "minsizerel",
-            }
-        }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn build_info_round_trips_through_json() {
+        let info = full_build_info();
+        let json = info.to_json().unwrap();
+        assert_eq!(BuildInfo::from_json(&json).unwrap(), info);
+        let empty = BuildInfo::default();
+        assert_eq!(
+            BuildInfo::from_json(&empty.to_json().unwrap()).unwrap(),
+            empty
+        );
     }
 
-    fn xmake_command(&mut self) -> Command {
-        let mut cmd = Command::new(self.xmake_executable());
-        cmd.current_dir(self.path.as_path());
+    #[test]
+    fn cross_compiling_passes_the_arch() {
+        let riscv = Triple::parse("riscv64gc-unknown-linux-gnu");
+        assert_eq!(
+            plat_args("linux", &riscv, true, None),
+            ["--plat=linux", "--arch=riscv64"]
+        );
+        // xmake already builds for the host
+        assert_eq!(plat_args("linux", &riscv, false, None), ["--plat=linux"]);
+        let android = Triple::parse("armv7-linux-androideabi");
+        assert_eq!(
+            plat_args("android", &android, true, None),
+            ["--plat=android", "--arch=armeabi-v7a"]
+        );
+        // The cross platform takes the architecture from --cross
+        let bare = Triple::parse("thumbv7em-none-eabihf");
+        assert_eq!(plat_args("cross", &bare, true, None), ["--plat=cross"]);
+        // An architecture of a universal build wins
+        let macos = Triple::parse("aarch64-apple-darwin");
+        assert_eq!(
+            plat_args("macosx", &macos, false, Some("x86_64")),
+            ["--plat=macosx", "--arch=x86_64"]
+        );
+        assert_eq!(
+            plat_args("macosx", &macos, true, Some("x86_64")),
+            ["--plat=macosx", "--arch=x86_64"]
+        );
+    }
 
-        // Add envs
-        for &(ref k, ref v) in self.env.iter().chain(&self.env) {
-            cmd.env(k, v);
+    #[test]
+    fn sensitive_variables() {
+        for name in [
+            "GITHUB_TOKEN",
+            "AWS_SECRET_ACCESS_KEY",
+            "npm_config_password",
+            "KEY",
+            "SERVICE_APIKEY",
+            "NPM_AUTH",
+            "GOOGLE_APPLICATION_CREDENTIALS",
+        ] {
+            assert!(is_sensitive(name), "{}", name);
         }
+        for name in [
+            "AUTHOR",
+            "KEYBOARD_LAYOUT",
+            "MONKEY",
+            "PATH",
+            "XMAKE_GLOBALDIR",
+        ] {
+            assert!(!is_sensitive(name), "{}", name);
+        }
+    }
 
-        // Set the project dir env for xmake
-        cmd.env("XMAKE_PROJECT_DIR", self.path.clone());
-        cmd
+    #[test]
+    fn option_values_are_formatted() {
+        let mut config = Config::new(".");
+        config
+            .option("bar", true)
+            .option("baz", false)
+            .option("jobs", 4)
+            .option("offset", -2i32)
+            .option("name", "foo bar")
+            .option("archs", "x86_64,arm64")
+            .option("sdk", Path::new("/opt/sdk"))
+            .option("prefix", PathBuf::from("/usr/local"));
+        assert_eq!(
+            config.option_args(&[]),
+            [
+                "--bar=y",
+                "--baz=n",
+                "--jobs=4",
+                "--offset=-2",
+                "--name=foo bar",
+                "--archs=x86_64,arm64",
+                "--sdk=/opt/sdk",
+                "--prefix=/usr/local",
+            ]
+        );
     }
 
-    fn xmake_executable(&mut self) -> OsString {
-        self.getenv_os("XMAKE")
-            .unwrap_or_else(|| OsString::from("xmake"))
+    #[test]
+    fn option_values_accept_os_strings_and_wide_integers() {
+        let dir = PathBuf::from("/opt/sdk");
+        let name = OsString::from("foo");
+        let mut config = Config::new(".");
+        config
+            .option("sdk", &dir)
+            .option("name", &name)
+            .option("title", Cow::Borrowed("bar"))
+            .option("size", u64::MAX)
+            .option("count", usize::MAX)
+            .option("delta", isize::MIN);
+        assert_eq!(
+            config.option_args(&[]),
+            [
+                OsString::from("--sdk=/opt/sdk"),
+                OsString::from("--name=foo"),
+                OsString::from("--title=bar"),
+                OsString::from(format!("--size={}", u64::MAX)),
+                OsString::from(format!("--count={}", usize::MAX)),
+                OsString::from(format!("--delta={}", isize::MIN)),
+            ]
+        );
     }
 
-    fn getenv_os(&mut self, v: &str) -> Option<OsString> {
-        if let Some(val) = self.env_cache.get(v) {
-            return val.clone();
-        }
-        let r = env::var_os(v);
-        println!("{} = {:?}", v, r);
-        self.env_cache.insert(v.to_string(), r.clone());
-        r
+    #[cfg(unix)]
+    #[test]
+    fn option_values_keep_non_utf8_bytes() {
+        use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+        let value = OsString::from_vec(b"caf\xe9".to_vec());
+        let mut config = Config::new(".");
+        config
+            .option("name", &value)
+            .target_option("foo", "dir", Path::new(&value));
+        let args = config.option_args(&["-g1"]);
+        assert_eq!(args[0].as_bytes(), b"--name=caf\xe9");
+        assert_eq!(args[1], "--cxflags=-g1");
+        assert_eq!(args[2].as_bytes(), b"--dir=caf\xe9");
     }
-}
 
-fn run(cmd: &mut Command, program: &str) {
-    println!("running: {:?}", cmd);
-    let status = match cmd.status() {
-        Ok(status) => status,
-        Err(ref e) if e.kind() == ErrorKind::NotFound => {
-            fail(&format!(
-                "failed to execute command: {}\nis `{}` not installed?",
-                e, program
-            ));
+    #[test]
+    fn shared_build_cache_depends_on_the_configuration() {
+        let build_dir = |change: fn(&mut Config)| {
+            let mut config = Config::new(".");
+            config
+                .out_dir(env::temp_dir().join("xmakers-test-cache"))
+                .target_triple("x86_64-unknown-linux-gnu")
+                .mode("release")
+                .shared_build_cache(true);
+            change(&mut config);
+            config.get_build_dir()
+        };
+        let default = build_dir(|_| {});
+        assert_eq!(default, build_dir(|_| {}));
+        assert!(default.starts_with(env::temp_dir().join("xmakers-test-cache")));
+        for other in [
+            build_dir(|c| {
+                c.toolchain("clang");
+            }),
+            build_dir(|c| {
+                c.sanitizer(Sanitizer::Address);
+            }),
+            build_dir(|c| {
+                c.env("CFLAGS", "-O1");
+            }),
+            build_dir(|c| {
+                c.pic(true);
+            }),
+            build_dir(|c| {
+                c.target_option("foo", "bar", true);
+            }),
+            build_dir(|c| {
+                c.require_with_configs("zlib", &[]);
+            }),
+            build_dir(|c| {
+                c.mode("debug");
+            }),
+        ] {
+            assert_ne!(default, other);
         }
-        Err(e) => fail(&format!("failed to execute command: {}", e)),
-    };
-    if !status.success() {
-        fail(&format!(
-            "command did not execute successfully, got: {}",
-            status
-        ));
     }
-}
 
-fn getenv_unwrap(v: &str) -> String {
-    match env::var(v) {
-        Ok(s) => s,
-        Err(..) => fail(&format!("environment variable `{}` not defined", v)),
+    #[test]
+    fn compiler_flags_are_merged_with_the_user_ones() {
+        let mut config = Config::new(".");
+        assert_eq!(config.option_args(&["-g1"]), ["--cxflags=-g1"]);
+        config.option("cxflags", "-O1");
+        assert_eq!(
+            config.option_args(&["-g1", "-fPIC"]),
+            ["--cxflags=-O1 -g1 -fPIC"]
+        );
     }
-}
 
-fn fail(s: &str) -> ! {
-    panic!("\n{}\n\nbuild script failed, must exit now", s)
+    /// Parses the build information printed as `lines`, with empty values for
+    /// the other keys.
+    fn parse_info(lines: &str) -> BuildInfo {
+        let empty = "linkdirs:\nframeworkdirs:\nlinks:\nsyslinks:\npackages:\n";
+        format!("{}\n{}cxx_used:false\nstl_used:false", lines, empty)
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "not a buildable kind")]
+    fn unbuildable_kind_panics() {
+        Config::new(".").default_target_kind(LinkKind::Framework);
+    }
 }
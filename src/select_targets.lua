@@ -0,0 +1,73 @@
+-- Selects the targets the xmake-rs scripts report on, from the variables set
+-- by the crate. It is prepended to the scripts, which import
+-- `core.project.project`.
+
+-- Splits a comma separated list, in which `\,` and `\\` escape a comma and
+-- a backslash.
+function _split_list(str)
+    local items = {}
+    local item = ""
+    local escaped = false
+    for i = 1, #str do
+        local c = str:sub(i, i)
+        if escaped then
+            item = item .. c
+            escaped = false
+        elseif c == "\\" then
+            escaped = true
+        elseif c == "," then
+            table.insert(items, item)
+            item = ""
+        else
+            item = item .. c
+        end
+    end
+    table.insert(items, item)
+    return items
+end
+
+-- Splits a target address like `sub/dir:target` into the directory of the
+-- xmake.lua declaring the target and its name. The `::` of the namespaces
+-- isn't a separator.
+function _split_address(address)
+    for i = 1, #address do
+        if address:sub(i, i) == ":" and address:sub(i + 1, i + 1) ~= ":"
+                and (i == 1 or address:sub(i - 1, i - 1) ~= ":") then
+            return address:sub(1, i - 1), address:sub(i + 1)
+        end
+    end
+    return nil, address
+end
+
+-- Returns the targets selected by the crate or the targets of the selected
+-- group, or the default ones unless all of them are built.
+function _get_targets()
+    local targets = {}
+    local names = os.getenv("XMAKERS_TARGETS")
+    if names and #names > 0 then
+        for _, name in ipairs(_split_list(names)) do
+            local dir, targetname = _split_address(name)
+            local target = project.target(targetname)
+            if not target or (dir and path.absolute(dir, os.projectdir()) ~= path.absolute(target:scriptdir())) then
+                raise("unknown target: %s", name)
+            end
+            table.insert(targets, target)
+        end
+    else
+        local all = os.getenv("XMAKERS_ALL_TARGETS") == "y"
+        local group = os.getenv("XMAKERS_GROUP")
+        local pattern = group and ("^" .. path.pattern(group) .. "$")
+        for _, target in ipairs(project.ordertargets()) do
+            local selected
+            if pattern then
+                selected = (target:get("group") or ""):match(pattern) ~= nil
+            else
+                selected = all or target:is_default()
+            end
+            if selected then
+                table.insert(targets, target)
+            end
+        end
+    end
+    return targets
+end
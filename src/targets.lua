@@ -0,0 +1,18 @@
+-- Reports the targets of the project to xmake-rs.
+-- The output is printed between markers as `key:value1|value2` lines.
+import("core.project.config")
+import("core.project.project")
+
+function main()
+    os.cd(os.projectdir())
+    config.load()
+
+    local targets = {}
+    for _, target in ipairs(project.ordertargets()) do
+        table.insert(targets, target:name())
+    end
+
+    print("__xmakers_start__")
+    print("targets:" .. table.concat(targets, "|"))
+    print("__xmakers_end__")
+end
@@ -2,10 +2,16 @@ extern crate xmake;
 
 fn main() {
     // Builds the project in the directory located in `libdouble`, installing it
-    // into $OUT_DIR
-    let dst = xmake::build("libdouble");
-
-    println!("cargo:rustc-link-search=native={}", dst.display());
-    println!("cargo:rustc-link-lib=static=libdouble");
+    // into $OUT_DIR and linking the produced library
+    if cfg!(feature = "shared") {
+        // Builds a shared library instead, copied next to the binary so it
+        // runs without setting LD_LIBRARY_PATH
+        xmake::Config::new("libdouble")
+            .default_target_kind(xmake::LinkKind::Dynamic)
+            .rpath(xmake::RpathMode::Origin)
+            .copy_dylibs_to_target(true)
+            .build();
+    } else {
+        xmake::build("libdouble");
+    }
 }
-
@@ -1,3 +1,3 @@
 target("libdouble")
-    set_kind("static")
-    add_files("double.c")
\ No newline at end of file
+    set_kind("$(kind)")
+    add_files("double.c")